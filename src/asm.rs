@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{error, fmt, fs};
+
+use crate::decoder::{self, Instruction, Operand};
+use crate::disasm::START;
+use crate::io::MAX_ROM_SIZE;
+use crate::types::{AddressLong, Data};
+
+/// How deep includes and constants referring to other constants may go.
+const MAX_DEPTH: usize = 16;
+
+/// An error in the source, with where it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmErr {
+    pub file: String,
+    /// Starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl error::Error for AsmErr {}
+
+/// A source line along with the address and bytes it produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingLine {
+    pub address: AddressLong,
+    pub bytes: Vec<Data>,
+    pub source: String,
+}
+
+/// The result of a successful assembly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    /// Meant to be loaded at 0x200.
+    pub rom: Vec<Data>,
+    pub lines: Vec<ListingLine>,
+}
+
+impl Assembly {
+    /// One line per source line: address, bytes in hexadecimal and source.
+    pub fn listing(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            let bytes: Vec<String> = line.bytes.iter().map(|b| format!("{b:02X}")).collect();
+            out += format!("{:#06x}  {:<12} {}", line.address, bytes.join(" "), line.source).trim_end();
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Assembles Cowgod-style source, e.g. `LD V3, 0x10`. Includes are looked up
+/// from the current directory.
+pub fn assemble(source: &str) -> Result<Assembly, AsmErr> {
+    let mut assembler = Assembler::default();
+    assembler.read(source, "<input>", Path::new("."), 0)?;
+    assembler.finish()
+}
+
+/// Assembles a file, looking up its includes next to it.
+pub fn assemble_file<P: AsRef<Path>>(path: P) -> Result<Assembly, AsmErr> {
+    let path = path.as_ref();
+    let name = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| AsmErr { file: name.clone(), line: 0, message: e.to_string() })?;
+    let mut assembler = Assembler::default();
+    assembler.read(&source, &name, path.parent().unwrap_or(Path::new(".")), 0)?;
+    assembler.finish()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Number(i64),
+    Symbol(String),
+}
+
+/// Numbers and symbols added or subtracted together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Expr(Vec<(i64, Term)>);
+
+#[derive(Debug)]
+enum Statement {
+    /// A label, a constant, a comment or an empty line.
+    Nothing,
+    Instruction { mnemonic: String, operands: Vec<Operand<Expr>> },
+    Db(Vec<Expr>),
+    Dw(Vec<Expr>),
+}
+
+#[derive(Debug)]
+enum Symbol {
+    Label(AddressLong),
+    Constant(Expr),
+}
+
+#[derive(Debug)]
+struct Item {
+    file: String,
+    line: usize,
+    address: usize,
+    source: String,
+    statement: Statement,
+}
+
+#[derive(Debug, Default)]
+struct Assembler {
+    symbols: HashMap<String, Symbol>,
+    items: Vec<Item>,
+    size: usize,
+}
+
+impl Assembler {
+    /// First pass: parses the lines and gives every label its address.
+    fn read(&mut self, source: &str, file: &str, dir: &Path, depth: usize) -> Result<(), AsmErr> {
+        for (index, text) in source.lines().enumerate() {
+            let err = |message: String| AsmErr { file: file.to_string(), line: index + 1, message };
+            let code = text.split(';').next().unwrap_or_default().trim();
+            let (label, code) = split_label(code).map_err(err)?;
+            if let Some(label) = label {
+                self.define(label, Symbol::Label((START + self.size) as AddressLong)).map_err(err)?;
+            }
+
+            let (word, rest) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+            let rest = rest.trim();
+            let statement = match word.to_ascii_uppercase().as_str() {
+                "" => Statement::Nothing,
+                "DB" => Statement::Db(list(rest).map_err(err)?),
+                "DW" => Statement::Dw(list(rest).map_err(err)?),
+                "INCLUDE" => {
+                    if depth >= MAX_DEPTH {
+                        return Err(err("too many nested includes".to_string()));
+                    }
+                    let name = rest
+                        .strip_prefix('"')
+                        .and_then(|r| r.strip_suffix('"'))
+                        .ok_or_else(|| err("expected `INCLUDE \"file\"`".to_string()))?;
+                    let path: PathBuf = dir.join(name);
+                    let included = fs::read_to_string(&path).map_err(|e| err(format!("unable to read {}: {e}", path.display())))?;
+                    self.items.push(Item { file: file.to_string(), line: index + 1, address: self.size, source: text.to_string(), statement: Statement::Nothing });
+                    self.read(&included, &path.display().to_string(), path.parent().unwrap_or(dir), depth + 1)?;
+                    continue;
+                },
+                _ => match rest.split_once(char::is_whitespace) {
+                    Some((equ, value)) if equ.eq_ignore_ascii_case("EQU") && label.is_none() => {
+                        self.define(word, Symbol::Constant(expr(value.trim()).map_err(err)?)).map_err(err)?;
+                        Statement::Nothing
+                    },
+                    _ => Statement::Instruction {
+                        mnemonic: word.to_ascii_uppercase(),
+                        operands: decoder::operands(rest, expr).map_err(err)?,
+                    },
+                },
+            };
+
+            let size = match &statement {
+                Statement::Nothing => 0,
+                Statement::Instruction { operands, .. } if operands.iter().any(|o| matches!(o, Operand::Long(_))) => 4,
+                Statement::Instruction { .. } => 2,
+                Statement::Db(values) => values.len(),
+                Statement::Dw(values) => 2 * values.len(),
+            };
+            self.items.push(Item { file: file.to_string(), line: index + 1, address: self.size, source: text.to_string(), statement });
+            self.size += size;
+            if self.size > MAX_ROM_SIZE {
+                return Err(err(format!("the program is larger than {MAX_ROM_SIZE} bytes")));
+            }
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str, symbol: Symbol) -> Result<(), String> {
+        if !is_identifier(name) {
+            return Err(format!("`{name}` is not a valid name"));
+        }
+        if self.symbols.contains_key(name) {
+            return Err(format!("`{name}` is already defined"));
+        }
+        self.symbols.insert(name.to_string(), symbol);
+        Ok(())
+    }
+
+    /// Second pass: produces the bytes now that every symbol is known.
+    fn finish(self) -> Result<Assembly, AsmErr> {
+        let mut rom = Vec::with_capacity(self.size);
+        let mut lines = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            let err = |message: String| AsmErr { file: item.file.clone(), line: item.line, message };
+            let bytes = match &item.statement {
+                Statement::Nothing => Vec::new(),
+                Statement::Instruction { mnemonic, operands } => {
+                    let mut bytes = self.instruction(mnemonic, operands).map_err(err)?.encode().to_be_bytes().to_vec();
+                    if let [_, Operand::Long(address)] = &operands[..] {
+                        let address = address.as_ref().ok_or_else(|| err("`LONG` needs an address".to_string()))?;
+                        bytes.extend(self.fit(address, 16).map_err(err)?.to_be_bytes());
+                    }
+                    bytes
+                },
+                Statement::Db(values) => values
+                    .iter()
+                    .map(|v| self.fit(v, 8).map(|v| v as Data))
+                    .collect::<Result<_, _>>()
+                    .map_err(err)?,
+                Statement::Dw(values) => {
+                    let mut bytes = Vec::new();
+                    for value in values {
+                        bytes.extend(self.fit(value, 16).map_err(err)?.to_be_bytes());
+                    }
+                    bytes
+                },
+            };
+            rom.extend_from_slice(&bytes);
+            lines.push(ListingLine { address: (START + item.address) as AddressLong, bytes, source: item.source.clone() });
+        }
+        Ok(Assembly { rom, lines })
+    }
+
+    fn eval(&self, expr: &Expr, depth: usize) -> Result<i64, String> {
+        if depth > MAX_DEPTH {
+            return Err("constants refer to each other in a loop".to_string());
+        }
+        let mut total = 0i64;
+        for (sign, term) in &expr.0 {
+            let value = match term {
+                Term::Number(n) => *n,
+                Term::Symbol(name) => match self.symbols.get(name) {
+                    Some(Symbol::Label(address)) => *address as i64,
+                    Some(Symbol::Constant(expr)) => self.eval(expr, depth + 1)?,
+                    None => return Err(format!("`{name}` is not defined")),
+                },
+            };
+            total = sign.checked_mul(value).and_then(|value| total.checked_add(value)).ok_or("the expression overflows")?;
+        }
+        Ok(total)
+    }
+
+    /// Evaluates `expr` and checks that it fits in `bits` bits, see `decoder::fit`.
+    fn fit(&self, expr: &Expr, bits: u32) -> Result<u16, String> {
+        decoder::fit(self.eval(expr, 0)?, bits)
+    }
+
+    /// The instruction, the address following F000 being left to the caller.
+    fn instruction(&self, mnemonic: &str, operands: &[Operand<Expr>]) -> Result<Instruction, String> {
+        decoder::instruction(mnemonic, operands, |e, bits| self.fit(e, bits))
+    }
+}
+
+/// Splits `label: rest` and checks the label.
+fn split_label(code: &str) -> Result<(Option<&str>, &str), String> {
+    match code.split_once(':') {
+        Some((label, rest)) => {
+            let label = label.trim();
+            if !is_identifier(label) {
+                return Err(format!("`{label}` is not a valid label"));
+            }
+            Ok((Some(label), rest.trim()))
+        },
+        None => Ok((None, code)),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn list(text: &str) -> Result<Vec<Expr>, String> {
+    if text.is_empty() {
+        return Err("expected at least one value".to_string());
+    }
+    text.split(',').map(|value| expr(value.trim())).collect()
+}
+
+/// Parses `term (+|- term)*`, terms being numbers (decimal, 0x hexadecimal or
+/// 0b binary) or symbols.
+fn expr(text: &str) -> Result<Expr, String> {
+    let mut terms = Vec::new();
+    let mut sign = 1;
+    let mut rest = text.trim();
+    if let Some(r) = rest.strip_prefix('-') {
+        sign = -1;
+        rest = r.trim_start();
+    }
+    loop {
+        let end = rest.find(['+', '-']).unwrap_or(rest.len());
+        let word = rest[..end].trim();
+        terms.push((sign, term(word)?));
+        if end == rest.len() {
+            return Ok(Expr(terms));
+        }
+        sign = if rest[end..].starts_with('-') { -1 } else { 1 };
+        rest = &rest[end + 1..];
+    }
+}
+
+fn term(word: &str) -> Result<Term, String> {
+    if let Some(number) = decoder::number(word) {
+        number.map(Term::Number)
+    } else if is_identifier(word) {
+        Ok(Term::Symbol(word.to_string()))
+    } else {
+        Err(if word.is_empty() { "missing value".to_string() } else { format!("invalid value `{word}`") })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_can_be_used_before_they_are_defined() {
+        let source = "\
+SPEED EQU 2
+start:  LD I, sprite   ; the sprite below
+        LD V0, SPEED + 1
+loop:   DRW V0, V1, 3
+        JP loop
+sprite: DB 0b11100000, 0xA0, 224
+        DW start
+";
+        let assembly = assemble(source).unwrap();
+        assert_eq!(
+            assembly.rom,
+            [0xA2, 0x08, 0x60, 0x03, 0xD0, 0x13, 0x12, 0x04, 0xE0, 0xA0, 0xE0, 0x02, 0x00]
+        );
+    }
+
+    #[test]
+    fn every_form_in_the_decoder_comments_assembles() {
+        let source = "\
+CLS
+RET
+SCD 4
+SCU 2
+SCR
+SCL
+EXIT
+LOW
+HIGH
+JP V0, 0x300
+SE V1, V2
+SNE V1, 0x10
+LD [I], V1 - V4
+LD V1 - V4, [I]
+SHR V3
+SHL V3, V4
+SUBN VA, VB
+RND V5, 0x0F
+DRW V1, V2, 0
+SKP V6
+SKNP V6
+LD I, LONG 0x1234
+PLANE 3
+AUDIO
+LD V7, DT
+LD V7, K
+LD DT, V7
+LD ST, V7
+ADD I, V7
+LD F, V7
+LD HF, V7
+LD B, V7
+PITCH V7
+LD [I], V7
+LD V7, [I]
+LD R, V7
+LD V7, R
+";
+        let words: Vec<u16> = assemble(source)
+            .unwrap()
+            .rom
+            .chunks(2)
+            .map(|w| u16::from_be_bytes([w[0], w[1]]))
+            .collect();
+        assert_eq!(
+            words,
+            [
+                0x00E0, 0x00EE, 0x00C4, 0x00D2, 0x00FB, 0x00FC, 0x00FD, 0x00FE, 0x00FF, 0xB300, 0x5120, 0x4110,
+                0x5142, 0x5143, 0x8336, 0x834E, 0x8AB7, 0xC50F, 0xD120, 0xE69E, 0xE6A1, 0xF000, 0x1234, 0xF301,
+                0xF002, 0xF707, 0xF70A, 0xF715, 0xF718, 0xF71E, 0xF729, 0xF730, 0xF733, 0xF73A, 0xF755, 0xF765,
+                0xF775, 0xF785,
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_give_the_line() {
+        let err = assemble("CLS\n\nLD V0, 256").unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (3, "256 does not fit in 8 bits"));
+        assert_eq!(assemble("JP nowhere").unwrap_err().message, "`nowhere` is not defined");
+        assert_eq!(assemble("FOO V1").unwrap_err().message, "unknown instruction `FOO`");
+        assert_eq!(assemble("LD DT, 3").unwrap_err().message, "invalid operands for `LD`");
+        assert_eq!(assemble("a: CLS\na: CLS").unwrap_err().line, 2);
+        let err = assemble("CLS\nLD V0, 9223372036854775807 + 1").unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (2, "the expression overflows"));
+        let err = assemble("CLS\nLD V0, 0x10000000000000000").unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (2, "`0x10000000000000000` is too large"));
+    }
+
+    #[test]
+    fn listing_maps_lines_to_addresses_and_bytes() {
+        let assembly = assemble("; start\nloop: JP loop\nDB 1, 2").unwrap();
+        assert_eq!(assembly.listing(), "0x0200               ; start\n0x0200  12 00        loop: JP loop\n0x0202  01 02        DB 1, 2\n");
+    }
+
+    #[test]
+    fn includes_are_read_next_to_the_source() {
+        let dir = std::env::temp_dir().join(format!("c8-asm-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.asm"), "CALL sub\nEXIT\nINCLUDE \"sub.asm\"\n").unwrap();
+        fs::write(dir.join("sub.asm"), "sub: RET\n").unwrap();
+        let assembly = assemble_file(dir.join("main.asm"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(assembly.unwrap().rom, [0x22, 0x04, 0x00, 0xFD, 0x00, 0xEE]);
+    }
+}
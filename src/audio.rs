@@ -0,0 +1,206 @@
+use std::io::{self, Seek, SeekFrom, Write};
+use std::str::FromStr;
+
+use crate::io::{pattern_rate, Audio};
+use crate::types::Data;
+
+/// The shape of the tone played while the sound timer is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Waveform {
+    #[default]
+    Square,
+    Triangle,
+    Sine,
+    Sawtooth,
+}
+
+impl FromStr for Waveform {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "square" => Ok(Waveform::Square),
+            "triangle" => Ok(Waveform::Triangle),
+            "sine" => Ok(Waveform::Sine),
+            "sawtooth" | "saw" => Ok(Waveform::Sawtooth),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneConfig {
+    /// In Hz.
+    pub frequency: f64,
+    /// From 0 to 1.
+    pub volume: f64,
+    pub waveform: Waveform,
+}
+
+impl Default for ToneConfig {
+    fn default() -> Self {
+        ToneConfig { frequency: 440.0, volume: 0.25, waveform: Waveform::Square }
+    }
+}
+
+/// Generates the samples of the tone, or of the XO-CHIP pattern once a program sets one.
+#[derive(Debug, Clone)]
+pub struct Tone {
+    config: ToneConfig,
+    pattern: Option<([Data; 16], Data)>,
+    /// Position in the current period, from 0 to 1.
+    phase: f64,
+}
+
+impl Tone {
+    pub fn new(config: ToneConfig) -> Tone {
+        Tone { config, pattern: None, phase: 0.0 }
+    }
+
+    pub fn set_pattern(&mut self, pattern: &[Data; 16], pitch: Data) {
+        self.pattern = Some((*pattern, pitch));
+    }
+
+    /// The next sample, from -volume to volume.
+    pub fn sample(&mut self, sample_rate: u32) -> f64 {
+        let (value, frequency) = match self.pattern {
+            Some((pattern, pitch)) => {
+                let bit = (self.phase * 128.0) as usize % 128;
+                let on = pattern[bit / 8] >> (7 - bit % 8) & 1 == 1;
+                (if on { 1.0 } else { -1.0 }, pattern_rate(pitch) / 128.0)
+            },
+            None => {
+                let value = match self.config.waveform {
+                    Waveform::Square => if self.phase < 0.5 { 1.0 } else { -1.0 },
+                    Waveform::Triangle => 4.0 * (self.phase - 0.5).abs() - 1.0,
+                    Waveform::Sine => (self.phase * std::f64::consts::TAU).sin(),
+                    Waveform::Sawtooth => 2.0 * self.phase - 1.0,
+                };
+                (value, self.config.frequency)
+            },
+        };
+        self.phase = (self.phase + frequency / sample_rate as f64).fract();
+        value * self.config.volume
+    }
+}
+
+/// Records the tone to a 16-bit mono WAV file, one frame of samples every 1/60 s
+/// of emulated time. The header is completed when the writer is dropped or finished.
+pub struct WavWriter<W: Write + Seek> {
+    out: Option<W>,
+    tone: Tone,
+    playing: bool,
+    samples: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub const SAMPLE_RATE: u32 = 44100;
+
+    pub fn new(mut out: W, config: ToneConfig) -> io::Result<WavWriter<W>> {
+        WavWriter::<W>::write_header(&mut out, 0)?;
+        Ok(WavWriter { out: Some(out), tone: Tone::new(config), playing: false, samples: 0 })
+    }
+
+    /// Completes the header and gives back the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.complete()?;
+        Ok(self.out.take().unwrap())
+    }
+
+    fn write_header(out: &mut W, samples: u32) -> io::Result<()> {
+        let data = samples * 2;
+        out.write_all(b"RIFF")?;
+        out.write_all(&(36 + data).to_le_bytes())?;
+        out.write_all(b"WAVEfmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?; // PCM
+        out.write_all(&1u16.to_le_bytes())?; // mono
+        out.write_all(&Self::SAMPLE_RATE.to_le_bytes())?;
+        out.write_all(&(Self::SAMPLE_RATE * 2).to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?; // bytes per sample
+        out.write_all(&16u16.to_le_bytes())?; // bits per sample
+        out.write_all(b"data")?;
+        out.write_all(&data.to_le_bytes())
+    }
+
+    fn complete(&mut self) -> io::Result<()> {
+        let samples = self.samples;
+        if let Some(out) = self.out.as_mut() {
+            out.seek(SeekFrom::Start(0))?;
+            WavWriter::<W>::write_header(out, samples)?;
+            out.seek(SeekFrom::End(0))?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write_frame(&mut self) -> io::Result<()> {
+        let Some(out) = self.out.as_mut() else { return Ok(()) };
+        for _ in 0..Self::SAMPLE_RATE / 60 {
+            let sample = if self.playing { self.tone.sample(Self::SAMPLE_RATE) } else { 0.0 };
+            out.write_all(&((sample * i16::MAX as f64) as i16).to_le_bytes())?;
+            self.samples += 1;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Audio for WavWriter<W> {
+    fn start(&mut self) {
+        self.playing = true;
+    }
+
+    fn stop(&mut self) {
+        self.playing = false;
+    }
+
+    fn set_pattern(&mut self, pattern: &[Data; 16], pitch: Data) {
+        self.tone.set_pattern(pattern, pitch);
+    }
+
+    fn frame(&mut self) {
+        // The trait cannot report errors, stop recording on the first one.
+        if self.write_frame().is_err() {
+            self.out = None;
+        }
+    }
+}
+
+impl<W: Write + Seek> Drop for WavWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.complete();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn square_wave_alternates_every_half_period() {
+        let mut tone = Tone::new(ToneConfig { frequency: 1.0, volume: 0.5, waveform: Waveform::Square });
+        let samples: Vec<f64> = (0..4).map(|_| tone.sample(4)).collect();
+        assert_eq!(samples, [0.5, 0.5, -0.5, -0.5]);
+    }
+
+    #[test]
+    fn wav_writer_records_one_frame_of_samples_per_tick() {
+        let mut wav = WavWriter::new(Cursor::new(Vec::new()), ToneConfig::default()).unwrap();
+        wav.start();
+        wav.frame();
+        wav.stop();
+        wav.frame();
+        let bytes = wav.finish().unwrap().into_inner();
+
+        let per_frame = (WavWriter::<Cursor<Vec<u8>>>::SAMPLE_RATE / 60) as usize;
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(bytes.len(), 44 + 2 * 2 * per_frame);
+        assert_eq!(u32::from_le_bytes(bytes[40..44].try_into().unwrap()) as usize, 2 * 2 * per_frame);
+
+        let (tone, silence) = bytes[44..].split_at(2 * per_frame);
+        assert!(tone.iter().any(|b| *b != 0));
+        assert!(silence.iter().all(|b| *b == 0));
+    }
+}
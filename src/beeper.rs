@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use c8::audio::{Tone, ToneConfig};
+use c8::io::Audio;
+use c8::types::Data;
+
+struct State {
+    tone: Tone,
+    playing: bool,
+}
+
+/// Plays the tone on the default output device while the sound timer runs.
+pub struct Beeper {
+    state: Arc<Mutex<State>>,
+    _stream: cpal::Stream,
+}
+
+impl Beeper {
+    pub fn new(config: ToneConfig) -> Result<Beeper, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("no output device")?;
+        let supported = device.default_output_config().map_err(|e| e.to_string())?;
+        let sample_rate = supported.sample_rate().0;
+        let channels = supported.channels() as usize;
+
+        let state = Arc::new(Mutex::new(State { tone: Tone::new(config), playing: false }));
+        let shared = Arc::clone(&state);
+        let stream = device
+            .build_output_stream(
+                &supported.into(),
+                move |data: &mut [f32], _| {
+                    let mut state = shared.lock().unwrap();
+                    for frame in data.chunks_mut(channels) {
+                        let sample = if state.playing { state.tone.sample(sample_rate) as f32 } else { 0.0 };
+                        frame.fill(sample);
+                    }
+                },
+                |e| log::error!(target: "audio", "{e}"),
+                None,
+            )
+            .map_err(|e| e.to_string())?;
+        stream.play().map_err(|e| e.to_string())?;
+
+        Ok(Beeper { state, _stream: stream })
+    }
+}
+
+impl Audio for Beeper {
+    fn start(&mut self) {
+        self.state.lock().unwrap().playing = true;
+    }
+
+    fn stop(&mut self) {
+        self.state.lock().unwrap().playing = false;
+    }
+
+    fn set_pattern(&mut self, pattern: &[Data; 16], pitch: Data) {
+        self.state.lock().unwrap().tone.set_pattern(pattern, pitch);
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::Path;
+use std::{env, fs, process};
+
+use c8::asm;
+
+const USAGE: &str = "\
+Usage: c8-asm [OPTIONS] <SOURCE>
+
+Options:
+    -o, --output <FILE>     Where to write the ROM [default: SOURCE with a .ch8 extension]
+    -l, --listing <FILE>    Also write the address and bytes of every source line to FILE
+    -h, --help              Print this help";
+
+fn main() {
+    let mut source = None;
+    let mut output = None;
+    let mut listing = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            },
+            "-o" | "--output" => output = Some(args.next().unwrap_or_else(|| fail(&format!("option `{arg}` needs a value")))),
+            "-l" | "--listing" => listing = Some(args.next().unwrap_or_else(|| fail(&format!("option `{arg}` needs a value")))),
+            opt if opt.starts_with('-') && opt.len() > 1 => fail(&format!("unknown option `{opt}`")),
+            _ if source.is_some() => fail(&format!("unexpected argument `{arg}`")),
+            _ => source = Some(arg),
+        }
+    }
+
+    let Some(source) = source else { fail("no source file given") };
+    let assembly = match asm::assemble_file(&source) {
+        Ok(assembly) => assembly,
+        Err(e) => {
+            eprintln!("c8-asm: {e}");
+            process::exit(1);
+        },
+    };
+
+    let output = output.unwrap_or_else(|| Path::new(&source).with_extension("ch8").display().to_string());
+    write(&output, &assembly.rom);
+    if let Some(path) = listing {
+        write(&path, assembly.listing().as_bytes());
+    }
+}
+
+fn write(path: &str, contents: &[u8]) {
+    if let Err(e) = fs::write(path, contents) {
+        eprintln!("c8-asm: unable to write {path}: {e}");
+        process::exit(1);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("c8-asm: {message}\n\n{USAGE}");
+    process::exit(2);
+}
@@ -0,0 +1,50 @@
+use std::{env, process};
+
+use c8::disasm::{self, Syntax};
+use c8::io;
+
+const USAGE: &str = "\
+Usage: c8-disasm [OPTIONS] <ROM>
+
+Options:
+    -s, --syntax <SYNTAX>   cowgod or octo [default: cowgod]
+    -l, --linear            Decode every word instead of tracing the code from 0x200
+    -h, --help              Print this help";
+
+fn main() {
+    let mut syntax = Syntax::Cowgod;
+    let mut linear = false;
+    let mut rom = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            },
+            "-l" | "--linear" => linear = true,
+            "-s" | "--syntax" => match args.next().map(|value| value.parse()) {
+                Some(Ok(value)) => syntax = value,
+                _ => fail(&format!("option `{arg}` needs `cowgod` or `octo`")),
+            },
+            opt if opt.starts_with('-') && opt.len() > 1 => fail(&format!("unknown option `{opt}`")),
+            _ if rom.is_some() => fail(&format!("unexpected argument `{arg}`")),
+            _ => rom = Some(arg),
+        }
+    }
+
+    let Some(path) = rom else { fail("no ROM file given") };
+    match io::load(&path) {
+        Ok(rom) => print!("{}", disasm::disassemble(&rom, syntax, linear)),
+        Err(e) => {
+            eprintln!("c8-disasm: unable to load {path}: {e}");
+            process::exit(1);
+        },
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("c8-disasm: {message}\n\n{USAGE}");
+    process::exit(2);
+}
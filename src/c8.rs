@@ -1,107 +1,417 @@
-use std::thread;
-use std::time::Duration;
-
-use rand::Rng;
-
 use crate::decoder;
-use crate::io::{Screen, self};
-use crate::mem::{Memory};
+use crate::err::C8Err;
+use crate::io::{self, Audio, Display, Input, Keypad, NullAudio, NullDisplay, NullInput, Screen, SpriteEdge};
+use crate::mem::{self, Memory};
+use crate::quirks::Quirks;
+use crate::random::{Prng, RandomSource};
+use crate::trace::Tracer;
+use crate::state::{Reader, StateErr, Writer};
 
 use crate::stack::Stack;
 use crate::{types::*, timer::Timer};
 
+/// About 700 instructions per second.
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME : u32 = 11;
+
+/// What happened during a call to `Chip::cycle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// An instruction has been executed.
+    Executed,
+    /// The program has exited with 00FD, nothing has been executed.
+    Halted,
+    /// Fx0A is waiting for a key, the timers keep running.
+    WaitingForKey,
+}
+
+/// The state of an Fx0A instruction waiting for a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KeyWait {
+    register: Data,
+    /// The key pressed so far, stored once released.
+    pressed: Option<Data>,
+}
+
 pub struct Chip {
         pc          : AddressLong, // current address
         i           : AddressLong, // stores memory addresses
-        sp          : AddressShort, // stack pointer
         delay_t     : Timer,
         sound_t     : Timer,
         registers   : [Data; 16],
         stack       : Stack,
         memory      : Memory,
-        keys        : [Data; 16],
-    pub screen      : Screen
+        keys        : Keypad, // polled from the input at the start of every frame
+        screen      : Screen,
+        beeping     : bool,
+        display     : Box<dyn Display>,
+        input       : Box<dyn Input>,
+        audio       : Box<dyn Audio>,
+        quirks      : Quirks,
+        flags       : [Data; 16], // SUPER-CHIP RPL user flags, 16 on XO-CHIP
+        halted      : bool,
+        waiting     : Option<KeyWait>, // Fx0A in progress
+        pattern     : [Data; 16], // XO-CHIP audio pattern buffer
+        pitch       : Data,
+        per_frame   : u32, // instructions executed in 1/60 s
+        cycles      : u32, // instructions executed since the timers last ticked
+        rng         : Box<dyn RandomSource>,
+        tracer      : Option<Tracer>,
 }
 
 impl Chip {
-    pub fn new() -> Chip {
-        Chip { pc: 512, i: 0, sp: 0, delay_t: Timer::new(), sound_t: Timer::new(), registers: [0; 16], stack: Stack::new(), memory: Memory::new(), screen: Screen::new(), keys: [0; 16] }
+    pub fn new(display: Box<dyn Display>, input: Box<dyn Input>, audio: Box<dyn Audio>) -> Chip {
+        Chip { pc: 512, i: 0, delay_t: Timer::new(), sound_t: Timer::new(), registers: [0; 16], stack: Stack::new(), memory: Memory::new(), screen: Screen::new(), keys: Keypad::new(), beeping: false, display, input, audio, quirks: Quirks::default(), flags: [0; 16], halted: false, waiting: None, pattern: [0; 16], pitch: 64, per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME, cycles: 0, rng: Box::new(Prng::default()), tracer: None }
+    }
+
+    /// A started machine with `rom` loaded and no frontend, e.g. for tests.
+    pub fn from_rom(rom: &[Data]) -> Result<Chip, C8Err> {
+        let mut chip = Chip::new(Box::new(NullDisplay), Box::new(NullInput), Box::new(NullAudio));
+        chip.load(rom)?;
+        chip.start();
+        Ok(chip)
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+        self.screen.set_edge(if quirks.wrap_sprites { SpriteEdge::Wrap } else { SpriteEdge::Clip });
+    }
+
+    /// The RPL user flags saved by Fx75, which outlive the program using them.
+    pub fn flags(&self) -> [Data; 16] {
+        self.flags
+    }
+
+    /// Restores RPL user flags, e.g. the ones saved by a previous run.
+    pub fn set_flags(&mut self, flags: [Data; 16]) {
+        self.flags = flags;
+    }
+
+    /// True once the program has executed 00FD.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// The keys as last polled from the input.
+    pub fn keypad(&self) -> Keypad {
+        self.keys
+    }
+
+    /// The input overwrites the keys at the start of the next frame.
+    pub fn keypad_mut(&mut self) -> &mut Keypad {
+        &mut self.keys
+    }
+
+    /// True while Fx0A is waiting for a key.
+    pub fn is_waiting_for_key(&self) -> bool {
+        self.waiting.is_some()
+    }
+
+    /// Hands the current content of the screen to the display.
+    pub fn present(&mut self) {
+        self.display.present(&self.screen);
     }
 
     pub fn start(&mut self) {
         self.memory.load_font();
-        self.delay_t.set(100);
-        self.sound_t.set(100);
-        self.delay_t.start();
-        self.sound_t.start();
-    }
-
-    pub fn dump(&self) {
-        println!("\n================");
-        println!("Chip-8 Debug Dump");
-        println!("Program Counter: {:x?}", self.pc);
-        println!("I (Memory addresses): {:x?}", self.i);
-        println!("Stack Pointer: {:x?}", self.sp);
-        println!("Delay Timer: {:x?}", self.delay_t.get());
-        println!("Sound Timer: {:x?}", self.sound_t.get());
-        println!("Registers: {:x?}", self.registers);
-        println!("Stack: {:x?}", self.stack);
-        //println!("Memory: {:?}", self.memory);
-        println!("================\n");
-    } 
-
-    pub fn cycle(&mut self) {
+    }
+
+    /// How many instructions make up a 60 Hz frame, the timers tick once per frame.
+    pub fn instructions_per_frame(&self) -> u32 {
+        self.per_frame
+    }
+
+    pub fn set_instructions_per_frame(&mut self, per_frame: u32) {
+        self.per_frame = per_frame.max(1);
+    }
+
+    /// The address of the next instruction.
+    pub fn pc(&self) -> AddressLong {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: AddressLong) {
+        self.pc = pc;
+    }
+
+    pub fn i(&self) -> AddressLong {
+        self.i
+    }
+
+    pub fn set_i(&mut self, i: AddressLong) {
+        self.i = i;
+    }
+
+    /// V0 to VF.
+    pub fn registers(&self) -> [Data; 16] {
+        self.registers
+    }
+
+    /// Returns `false` if there is no register `register`.
+    pub fn set_register(&mut self, register: Data, value: Data) -> bool {
+        match self.registers.get_mut(register as usize) {
+            Some(r) => {
+                *r = value;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// The return addresses pushed by 2nnn, oldest first.
+    pub fn stack(&self) -> &[AddressLong] {
+        self.stack.entries()
+    }
+
+    /// Replaces the return addresses, oldest first. Returns `false`, leaving the
+    /// stack untouched, if there are more than 16.
+    pub fn set_stack(&mut self, entries: &[AddressLong]) -> bool {
+        let mut stack = Stack::new();
+        for entry in entries {
+            if stack.push(*entry).is_err() {
+                return false;
+            }
+        }
+        self.stack = stack;
+        true
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Reads and writes stay bounds checked.
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    /// Replaces the generator behind Cxkk, a seeded `random::Prng` by default.
+    pub fn set_random_source(&mut self, source: Box<dyn RandomSource>) {
+        self.rng = source;
+    }
+
+    /// Writes a line for every instruction executed from now on, `None` to stop.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// The state of the generator behind Cxkk, its seed before the first Cxkk.
+    pub fn random_state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    /// Seeds Cxkk, to make a run reproducible.
+    pub fn set_random_state(&mut self, state: u64) {
+        self.rng.set_state(state);
+    }
+
+    pub fn delay_timer(&self) -> Data {
+        self.delay_t.get()
+    }
+
+    pub fn sound_timer(&self) -> Data {
+        self.sound_t.get()
+    }
+
+    /// Describes the registers, timers and stack, one per line, for crash reports.
+    pub fn dump(&self) -> String {
+        format!(
+            "Program Counter: {:x?}\n\
+             I (Memory addresses): {:x?}\n\
+             Delay Timer: {:x?}\n\
+             Sound Timer: {:x?}\n\
+             Registers: {:x?}\n\
+             Stack: {:x?}\n",
+            self.pc,
+            self.i,
+            self.delay_t.get(),
+            self.sound_t.get(),
+            self.registers,
+            self.stack,
+        )
+    }
+
+    /// Runs cycles until `done` returns true after one of them, or the program halts.
+    /// Returns the number of cycles run, or `C8Err::CycleLimit` once `limit` cycles
+    /// have run without either happening.
+    pub fn run_until<F: FnMut(&Chip) -> bool>(&mut self, limit: u64, mut done: F) -> Result<u64, C8Err> {
+        let mut cycles = 0;
+        while !self.halted {
+            if cycles == limit {
+                return Err(C8Err::CycleLimit { cycles });
+            }
+            self.cycle()?;
+            cycles += 1;
+            if done(self) {
+                break;
+            }
+        }
+        Ok(cycles)
+    }
+
+    /// Runs `frames` frames of `instructions_per_frame` cycles, stopping early if
+    /// the program halts. The display is left to the caller, see `present`.
+    pub fn run_frames(&mut self, frames: u32) -> Result<(), C8Err> {
+        for _ in 0..frames as u64 * self.per_frame as u64 {
+            if self.cycle()? == StepOutcome::Halted {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fetches, decodes and executes one instruction.
+    pub fn cycle(&mut self) -> Result<StepOutcome, C8Err> {
+        if self.halted {
+            return Ok(StepOutcome::Halted);
+        }
+        if self.cycles == 0 {
+            self.input.poll(&mut self.keys);
+        }
+        if let Some(wait) = self.waiting {
+            self.wait_for_key(wait);
+            self.count_cycle();
+            self.update_audio();
+            return Ok(if self.waiting.is_some() { StepOutcome::WaitingForKey } else { StepOutcome::Executed });
+        }
+        if let Some(tracer) = &mut self.tracer {
+            if let Err(e) = tracer.instruction(&self.memory.vector, self.pc, self.i, &self.registers) {
+                log::error!(target: "cpu", "tracing stopped: {e}");
+                self.tracer = None;
+            }
+        }
         // fetch + decode
-        let read = decoder::decode(self.read2());
-        println!("Instruction: {:x?}", read);
+        let read = decoder::decode(self.read2()?);
         // execute 
-        self.execute(read);
+        self.execute(read)?;
+        self.count_cycle();
+        self.update_audio();
+        Ok(StepOutcome::Executed)
     }
 
-    fn read(&mut self) -> Data {
-        let val = self.memory.get(self.pc as usize).unwrap();
+    /// Polls the keypad for a pending Fx0A, storing the key in Vx once it has been
+    /// pressed and released, or only pressed with the `key_wait_on_press` quirk.
+    fn wait_for_key(&mut self, mut wait: KeyWait) {
+        let key = match wait.pressed {
+            Some(key) if self.keys.is_down(key) => return,
+            Some(key) => key,
+            None => {
+                let Some(key) = self.keys.first_down() else { return };
+                if !self.quirks.key_wait_on_press {
+                    wait.pressed = Some(key);
+                    self.waiting = Some(wait);
+                    return;
+                }
+                key
+            },
+        };
+        self.registers[wait.register as usize] = key;
+        self.waiting = None;
+    }
+
+    /// Ticks the timers once every `per_frame` instructions.
+    fn count_cycle(&mut self) {
+        self.cycles += 1;
+        if self.cycles >= self.per_frame {
+            self.cycles = 0;
+            self.delay_t.tick();
+            self.sound_t.tick();
+            self.audio.frame();
+            self.rng.frame();
+            log::trace!(target: "timer", "frame: delay {}, sound {}", self.delay_t.get(), self.sound_t.get());
+        }
+    }
+
+    /// Starts or stops the tone whenever the sound timer becomes active or expires.
+    fn update_audio(&mut self) {
+        let beeping = self.sound_t.get() > 0;
+        if beeping != self.beeping {
+            if beeping {
+                self.audio.start();
+            } else {
+                self.audio.stop();
+            }
+            self.beeping = beeping;
+        }
+    }
+
+    fn read(&mut self) -> Result<Data, C8Err> {
+        let val = self.memory.get(self.pc as usize)?;
         //println!("{}: {val}", self.pc);
-        self.pc += 1;
-        val as Data
+        self.pc = self.pc.wrapping_add(1);
+        Ok(val as Data)
     }
 
-    fn read2(&mut self) -> AddressLong {
-        print!("PC: {:x?} \t", self.pc);
-        let first = self.read();
-        let second = self.read();
+    fn read2(&mut self) -> Result<AddressLong, C8Err> {
+        let pc = self.pc;
+        let first = self.read()?;
+        let second = self.read()?;
         let first = (first as u16) << 8;
         let combined = first + second as u16;
-        println!("{:x?}", combined);
-        combined
+        log::trace!(target: "cpu", "{pc:04x}: {combined:04x}");
+        Ok(combined)
+    }
+
+    /// Writes the result of 8xy4 to 8xyE, then VF, so that the flag wins when x is F.
+    fn set_with_flag(&mut self, register: Data, result: Data, flag: bool) {
+        self.registers[register as usize] = result;
+        self.registers[0xF] = flag as Data;
     }
 
-    fn read_sprite(from: AddressLong, mem_vec: &Memory, amount: Data) -> &[u8] {
-        &mem_vec.vector[(from as usize) .. (from as usize + amount as usize)]
+    /// Skips the next instruction, which is 4 bytes long if it is XO-CHIP's F000.
+    fn skip(&mut self) {
+        let next = self.memory.get(self.pc as usize).unwrap_or(0) as u16 * 0x100
+            + self.memory.get(self.pc as usize + 1).unwrap_or(0) as u16;
+        self.pc = self.pc.wrapping_add(if next == 0xF000 { 4 } else { 2 });
     }
 
-    fn execute(&mut self, instr: decoder::Instruction){
+    fn read_sprite(from: AddressLong, mem_vec: &Memory, amount: usize) -> Result<&[u8], C8Err> {
+        let end = from as usize + amount;
+        mem_vec.vector.get((from as usize) .. end)
+            .ok_or(C8Err::ReadOutOfRange { address: end.min(mem_vec.vector.len()) })
+    }
+
+    /// Executes `instr` as if it had just been fetched: the PC already points
+    /// past it, and F000 reads its address at the PC. Timers, input and audio
+    /// are left alone, they belong to `cycle`.
+    pub fn execute(&mut self, instr: decoder::Instruction) -> Result<(), C8Err> {
         match instr {
             decoder::Instruction::Cls => { self.screen.clear(); },
             decoder::Instruction::Ret => {                
-                let val = self.stack.pop().expect("unable to pop values from stack");
+                let val = self.stack.pop()?;
                 self.pc = val; 
             },
 
+            decoder::Instruction::ScrollDown { rows } => { self.screen.scroll_down(rows as usize); },
+            decoder::Instruction::ScrollUp { rows } => { self.screen.scroll_up(rows as usize); },
+            decoder::Instruction::ScrollRight => { self.screen.scroll_right(4); },
+            decoder::Instruction::ScrollLeft => { self.screen.scroll_left(4); },
+            decoder::Instruction::Exit => { self.halted = true; },
+            decoder::Instruction::LowResolution => { self.screen.set_hires(false); },
+            decoder::Instruction::HighResolution => { self.screen.set_hires(true); },
+
             decoder::Instruction::Jump { location } => { self.pc = location; },
             decoder::Instruction::Call { location } => {
-                self.stack.push(self.pc);
+                self.stack.push(self.pc)?;
                 self.pc = location;
             },
 
             decoder::Instruction::SkipEqualRegisterBytes { register_index, bytes } => {
                 if *self.registers.get(register_index as usize).unwrap() == bytes {
-                    self.pc += 2;
+                    self.skip();
                 }
             },
 
             decoder::Instruction::SkipNotEqualRegisterBytes { register_index, bytes } => {
                 if *self.registers.get(register_index as usize).unwrap() != bytes {
-                    self.pc += 2;
+                    self.skip();
                 }
             },
 
@@ -109,7 +419,19 @@ impl Chip {
                 let register_x = self.registers.get(register_x as usize).unwrap();
                 let register_y = self.registers.get(register_y as usize).unwrap();
                 if register_x == register_y {
-                    self.pc += 2;
+                    self.skip();
+                }
+            },
+
+            decoder::Instruction::StoreRegisterRange { register_x, register_y } => {
+                for (offset, register) in Chip::register_range(register_x, register_y).enumerate() {
+                    self.memory.write(self.registers[register], self.i as usize + offset)?;
+                }
+            },
+
+            decoder::Instruction::LoadRegisterRange { register_x, register_y } => {
+                for (offset, register) in Chip::register_range(register_x, register_y).enumerate() {
+                    self.registers[register] = self.memory.get(self.i as usize + offset)?;
                 }
             },
 
@@ -118,7 +440,9 @@ impl Chip {
             },
 
             decoder::Instruction::AddBytesToRegister { register, bytes } => {
-                *self.registers.get_mut(register as usize).unwrap() += bytes;
+                // Wraps around without touching VF.
+                let value = self.registers.get_mut(register as usize).unwrap();
+                *value = value.wrapping_add(bytes);
             },
 
             decoder::Instruction::SetRegisterToRegister { register_x, register_y } => {
@@ -131,6 +455,9 @@ impl Chip {
                         let regyval = *self.registers.get(register_y as usize).unwrap();
                         *self.registers.get_mut(register_x as usize).unwrap() = 
                             regxval | regyval;
+                if self.quirks.logic_resets_vf {
+                    self.registers[0xF] = 0;
+                }
             },
 
             decoder::Instruction::BitwiseAnd { register_x, register_y } => {
@@ -138,6 +465,9 @@ impl Chip {
                         let regyval = *self.registers.get(register_y as usize).unwrap();
                         *self.registers.get_mut(register_x as usize).unwrap() = 
                             regxval & regyval;
+                if self.quirks.logic_resets_vf {
+                    self.registers[0xF] = 0;
+                }
             },
 
             decoder::Instruction::BitwiseXor { register_x, register_y } => {
@@ -145,118 +475,121 @@ impl Chip {
                         let regyval = *self.registers.get(register_y as usize).unwrap();
                         *self.registers.get_mut(register_x as usize).unwrap() = 
                             regxval ^ regyval;
+                if self.quirks.logic_resets_vf {
+                    self.registers[0xF] = 0;
+                }
             },
 
             decoder::Instruction::AddRegisterToRegister { register_x, register_y } => {
-                let regxval = *self.registers.get(register_x as usize).unwrap() as usize;
-                let regyval = *self.registers.get(register_y as usize).unwrap() as usize;
-                
-                let result = regxval + regyval;
-                if result > 0xFF {
-                    *self.registers.get_mut(register_x as usize).unwrap() = result as Data;
-                    *self.registers.get_mut(0xF as usize).unwrap() = (result - 0xFF) as Data;
-                } else {
-                    *self.registers.get_mut(register_y as usize).unwrap() = result as Data;
-                }
+                let regxval = *self.registers.get(register_x as usize).unwrap();
+                let regyval = *self.registers.get(register_y as usize).unwrap();
+                let (result, carry) = regxval.overflowing_add(regyval);
+                self.set_with_flag(register_x, result, carry);
             },
 
             decoder::Instruction::SubtractRegisterToRegister { register_x, register_y } => {
-                let regxval = *self.registers.get(register_x as usize).unwrap() as usize;
-                let regyval = *self.registers.get(register_y as usize).unwrap() as usize;
-                
-                let result = if regxval > regyval {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 1 as Data;
-                    (regxval - regyval) as Data
-                } else {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 0 as Data;
-                    0 as Data
-                };
-
-                *self.registers.get_mut(register_x as usize).unwrap() = result;
+                let regxval = *self.registers.get(register_x as usize).unwrap();
+                let regyval = *self.registers.get(register_y as usize).unwrap();
+                let (result, borrow) = regxval.overflowing_sub(regyval);
+                self.set_with_flag(register_x, result, !borrow);
             },
 
-            decoder::Instruction::LeastSignificantBit { register } => {
-                let regxval = *self.registers.get(register as usize).unwrap() as usize;
-                if regxval % 2 == 1 {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 1 as Data;
-                } else {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 0 as Data;
-                }
-                *self.registers.get_mut(register as usize).unwrap() = (regxval / 2) as Data;
+            decoder::Instruction::LeastSignificantBit { register_x, register_y } => {
+                let source = if self.quirks.shift_uses_vy { register_y } else { register_x };
+                let regval = *self.registers.get(source as usize).unwrap();
+                self.set_with_flag(register_x, regval >> 1, regval & 1 == 1);
             },
 
             decoder::Instruction::SubtractInversed { register_x, register_y } => {
-                let regxval = *self.registers.get(register_x as usize).unwrap() as usize;
-                let regyval = *self.registers.get(register_y as usize).unwrap() as usize;
-                
-                let result = if regyval > regxval {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 1 as Data;
-                    (regyval - regxval) as Data
-                } else {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 0 as Data;
-                    0 as Data
-                };
-
-                *self.registers.get_mut(register_x as usize).unwrap() = result;
+                let regxval = *self.registers.get(register_x as usize).unwrap();
+                let regyval = *self.registers.get(register_y as usize).unwrap();
+                let (result, borrow) = regyval.overflowing_sub(regxval);
+                self.set_with_flag(register_x, result, !borrow);
             },
 
-            decoder::Instruction::MostSignificantBit { register } => {
-                let regxval = *self.registers.get(register as usize).unwrap() as usize;
-                if (regxval >> 7) == 0b1  {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 1 as Data;
-                } else {
-                    *self.registers.get_mut(0xF as usize).unwrap() = 0 as Data;
-                }
-                *self.registers.get_mut(register as usize).unwrap() = (regxval * 2) as Data;
+            decoder::Instruction::MostSignificantBit { register_x, register_y } => {
+                let source = if self.quirks.shift_uses_vy { register_y } else { register_x };
+                let regval = *self.registers.get(source as usize).unwrap();
+                self.set_with_flag(register_x, regval << 1, regval >> 7 == 1);
             },
 
             decoder::Instruction::SkipNotEqualRegisterRegister { register_x, register_y } => {
                 let register_x = self.registers.get(register_x as usize).unwrap();
                 let register_y = self.registers.get(register_y as usize).unwrap();
                 if register_x != register_y {
-                    self.pc += 2;
+                    self.skip();
                 }
             },
             decoder::Instruction::SetI { value } => {
                 self.i = value;
             },
             decoder::Instruction::JumpToLocationPlusZeroRegister { address } => {
-                self.pc = address + (*self.registers.first().unwrap() as u16);
+                let register = if self.quirks.jump_uses_vx { (address >> 8) as usize } else { 0 };
+                self.pc = address + (*self.registers.get(register).unwrap() as u16);
             },
             decoder::Instruction::Random { register, value } => {
-                let x :u8 = rand::thread_rng().gen_range(0..=255) & value;
+                let x :u8 = self.rng.next_byte() & value;
                 *self.registers.get_mut(register as usize).unwrap() = x;
             },
             decoder::Instruction::Display { register_x, register_y, nibble } => {
-                let x = self.registers.get((register_x as usize)).unwrap();
+                let x = self.registers.get(register_x as usize).unwrap();
                 let y = self.registers.get(register_y as usize).unwrap();
-                let sprite = Chip::read_sprite(self.i, &self.memory, nibble);
-                self.screen.draw(*x as usize, *y as usize, sprite);
+                let amount = nibble as usize * self.screen.plane_count();
+                let sprite = Chip::read_sprite(self.i, &self.memory, amount)?;
+                let collision = self.screen.draw(*x as usize, *y as usize, sprite);
+                self.registers[0xF] = collision as Data;
+            },
+            decoder::Instruction::DisplayLarge { register_x, register_y } => {
+                let x = self.registers[register_x as usize];
+                let y = self.registers[register_y as usize];
+                let collision = if self.screen.is_hires() || (self.quirks.lores_large_sprites && !self.quirks.narrow_large_sprites) {
+                    let sprite = Chip::read_sprite(self.i, &self.memory, 32 * self.screen.plane_count())?;
+                    self.screen.draw_large(x as usize, y as usize, sprite)
+                } else if self.quirks.lores_large_sprites {
+                    let sprite = Chip::read_sprite(self.i, &self.memory, 16 * self.screen.plane_count())?;
+                    self.screen.draw(x as usize, y as usize, sprite)
+                } else {
+                    false
+                };
+                self.registers[0xF] = collision as Data;
             },
             decoder::Instruction::SkipIfKeyIsPressed { register } => {
                 /*
                 Skip next instruction if key with the value of Vx is pressed.
                 Checks the keyboard, and if the key corresponding to the value of Vx is currently in the down position, PC is increased by 2. 
                 */
-                let key_wanted = *self.registers.get(register as usize).unwrap();
-                if self.screen.window.is_key_down(io::u8_to_key(key_wanted)) {
-                    self.pc += 2;
+                let key_wanted = Chip::key(*self.registers.get(register as usize).unwrap())?;
+                if self.keys.is_down(key_wanted) {
+                    self.skip();
                 }
             },
             decoder::Instruction::SkipIfKeyIsNotPressed { register } => {
-                let key_wanted = *self.registers.get(register as usize).unwrap();
-                if !self.screen.window.is_key_down(io::u8_to_key(key_wanted)) {
-                    self.pc += 2;
+                let key_wanted = Chip::key(*self.registers.get(register as usize).unwrap())?;
+                if !self.keys.is_down(key_wanted) {
+                    self.skip();
+                }
+            },
+            decoder::Instruction::SetILong => {
+                self.i = self.read2()?;
+            },
+            decoder::Instruction::SelectPlanes { planes } => {
+                self.screen.set_planes(planes);
+            },
+            decoder::Instruction::LoadAudioPattern => {
+                for (offset, byte) in self.pattern.iter_mut().enumerate() {
+                    *byte = self.memory.get(self.i as usize + offset).unwrap_or(0);
                 }
+                self.audio.set_pattern(&self.pattern, self.pitch);
+            },
+            decoder::Instruction::SetPitch { register } => {
+                self.pitch = self.registers[register as usize];
+                self.audio.set_pattern(&self.pattern, self.pitch);
             },
             decoder::Instruction::SetRegisterToDelayTimer { register } => {
                 *self.registers.get_mut(register as usize).unwrap() = self.delay_t.get();
             },
             decoder::Instruction::WaitForKey { register } => {
-                if self.keys.is_empty() {
-                    println!("è voto!");
-                    self.pc -= 1;
-                }
+                self.waiting = Some(KeyWait { register, pressed: None });
             },
             decoder::Instruction::SetDelayTimer { register } => {
                 self.delay_t.set(
@@ -269,12 +602,16 @@ impl Chip {
                 )
             },
             decoder::Instruction::AddRegisterToI { register } => {
-                self.i += *self.registers.get(register as usize).unwrap() as u16;
+                self.i = self.i.wrapping_add(*self.registers.get(register as usize).unwrap() as u16);
             },
             decoder::Instruction::SetIToLocationOfSprite { register } => {
                 let ch = *self.registers.get(register as usize).unwrap();
                 self.i = self.memory.get_font(ch) as u16;
             },
+            decoder::Instruction::SetIToLocationOfLargeSprite { register } => {
+                let ch = *self.registers.get(register as usize).unwrap();
+                self.i = self.memory.get_large_font(ch) as u16;
+            },
             decoder::Instruction::StoreBCD { register } => {
                 // Highly inspired by:
                 // https://github.com/taniarascia/chip8/blob/master/classes/CPU.js
@@ -285,35 +622,462 @@ impl Chip {
                 x -= b * 10;
                 let c = x;
 
-                self.memory.write(a, self.i as usize);
-                self.memory.write(b, (self.i + 1) as usize);
-                self.memory.write(c, (self.i + 2) as usize);
+                self.memory.write(a, self.i as usize)?;
+                self.memory.write(b, self.i as usize + 1)?;
+                self.memory.write(c, self.i as usize + 2)?;
             },
             decoder::Instruction::StoreRegistersToMemory { to_register } => {
                 for i in 0..=(to_register as usize) {
                     self.memory.write(
-                        *self.registers.get(i as usize).unwrap(),
-                        self.i as usize + i)
+                        *self.registers.get(i).unwrap(),
+                        self.i as usize + i)?;
+                }
+                if self.quirks.load_store_increments_i {
+                    self.i = self.i.wrapping_add(to_register as u16 + 1);
                 }
             },
             decoder::Instruction::LoadRegistersFromMemory { to_register } => {
                 for i in 0..=(to_register as usize) {
                     *self.registers.get_mut(i).unwrap() = 
-                        self.memory.get(self.i as usize + i).unwrap()
+                        self.memory.get(self.i as usize + i)?;
+                }
+                if self.quirks.load_store_increments_i {
+                    self.i = self.i.wrapping_add(to_register as u16 + 1);
                 }
             },
-            decoder::Instruction::Invalid => {
-                panic!("Invalid instruction");
+            decoder::Instruction::StoreRegistersToFlags { to_register } => {
+                let last = (to_register as usize).min(self.flags.len() - 1);
+                self.flags[..=last].copy_from_slice(&self.registers[..=last]);
             },
+            decoder::Instruction::LoadRegistersFromFlags { to_register } => {
+                let last = (to_register as usize).min(self.flags.len() - 1);
+                self.registers[..=last].copy_from_slice(&self.flags[..=last]);
+            },
+            decoder::Instruction::Invalid { opcode } => {
+                return Err(C8Err::InvalidOpcode { opcode, address: self.pc.wrapping_sub(2) });
+            },
+        }
+        Ok(())
+    }
+
+    /// Checks that a register holds one of the 16 keys.
+    fn key(value: Data) -> Result<Data, C8Err> {
+        match value {
+            0x0..=0xF => Ok(value),
+            _ => Err(C8Err::BadKey { key: value }),
+        }
+    }
+
+    /// The registers from x to y, counting backwards if y is lower.
+    fn register_range(x: Data, y: Data) -> Box<dyn Iterator<Item = usize>> {
+        let (x, y) = (x as usize, y as usize);
+        if x <= y {
+            Box::new(x..=y)
+        } else {
+            Box::new((y..=x).rev())
         }
     }
 
-    pub fn load(&mut self, filepath :&str) {
-        let buffer = io::load(filepath);
-        for (i, val) in buffer.iter().enumerate() {
-            self.memory.write(*val, i+512);
+    /// Serializes the whole machine: CPU, memory, screen, keypad and quirks.
+    /// See the `state` module for the header.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.u16(self.pc);
+        w.u16(self.i);
+        w.u8(self.delay_t.get());
+        w.u8(self.sound_t.get());
+        w.bytes(&self.registers);
+        w.u8(self.stack.entries().len() as u8);
+        for address in self.stack.entries() {
+            w.u16(*address);
+        }
+        w.bytes(&self.memory.vector);
+        w.bool(self.screen.is_hires());
+        w.u8(self.screen.planes());
+        w.bytes(self.screen.pixels());
+        w.u16(self.keys.bits());
+        w.u8(self.quirks.to_bits());
+        w.bytes(&self.flags);
+        w.bool(self.halted);
+        match self.waiting {
+            Some(KeyWait { register, pressed }) => {
+                w.bool(true);
+                w.u8(register);
+                w.u8(pressed.unwrap_or(0xFF));
+            },
+            None => w.bool(false),
         }
+        w.bytes(&self.pattern);
+        w.u8(self.pitch);
+        w.u32(self.per_frame);
+        w.u32(self.cycles);
+        w.u64(self.rng.state());
+        w.finish()
     }
 
+    /// Restores a state made by `save_state`. Nothing changes if it is rejected.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateErr> {
+        let mut r = Reader::open(state)?;
+        let (pc, i) = (r.u16()?, r.u16()?);
+        let (mut delay_t, mut sound_t) = (Timer::new(), Timer::new());
+        delay_t.set(r.u8()?);
+        sound_t.set(r.u8()?);
+        let registers = r.array()?;
+        let mut stack = Stack::new();
+        for _ in 0..r.u8()? {
+            stack.push(r.u16()?).map_err(|_| StateErr::Invalid { what: "stack deeper than 16" })?;
+        }
+        let memory = Memory { vector: r.bytes(mem::SIZE)?.to_vec() };
+        let mut screen = Screen::new();
+        screen.set_hires(r.bool()?);
+        screen.set_planes(r.u8()?);
+        let len = screen.pixels().len();
+        if !screen.set_pixels(r.bytes(len)?) {
+            return Err(StateErr::Invalid { what: "pixel out of range" });
+        }
+        let keys = Keypad::from_bits(r.u16()?);
+        let quirks = Quirks::from_bits(r.u8()?);
+        let flags = r.array()?;
+        let halted = r.bool()?;
+        let waiting = match r.bool()? {
+            true => {
+                let register = r.u8()?;
+                let pressed = r.u8()?;
+                if register > 0xF || (pressed > 0xF && pressed != 0xFF) {
+                    return Err(StateErr::Invalid { what: "key wait out of range" });
+                }
+                Some(KeyWait { register, pressed: (pressed != 0xFF).then_some(pressed) })
+            },
+            false => None,
+        };
+        let pattern = r.array()?;
+        let pitch = r.u8()?;
+        let per_frame = r.u32()?.max(1);
+        let cycles = r.u32()?;
+        let random = r.u64()?;
+        r.finish()?;
+
+        (self.pc, self.i) = (pc, i);
+        (self.delay_t, self.sound_t) = (delay_t, sound_t);
+        (self.registers, self.stack, self.memory) = (registers, stack, memory);
+        (self.screen, self.keys, self.flags) = (screen, keys, flags);
+        (self.halted, self.waiting, self.per_frame, self.cycles) = (halted, waiting, per_frame, cycles);
+        self.rng.set_state(random);
+        self.set_quirks(quirks);
+        if (pattern, pitch) != (self.pattern, self.pitch) {
+            (self.pattern, self.pitch) = (pattern, pitch);
+            self.audio.set_pattern(&pattern, pitch);
+        }
+        self.update_audio();
+        Ok(())
+    }
+
+    /// Copies a program read with `io::load` at 0x200.
+    pub fn load(&mut self, rom: &[Data]) -> Result<(), C8Err> {
+        if rom.len() > io::MAX_ROM_SIZE {
+            return Err(C8Err::RomTooLarge { size: rom.len() });
+        }
+        for (i, val) in rom.iter().enumerate() {
+            self.memory.write(*val, i+512)?;
+        }
+        Ok(())
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::StepOutcome;
+    use crate::decoder::Instruction::*;
+    use crate::headless::Headless;
+    use crate::quirks::Quirks;
+
+    #[test]
+    fn timers_tick_once_per_frame() {
+        let mut chip = Headless::new().chip();
+        chip.set_instructions_per_frame(4);
+        // LD V0, 3; LD DT, V0; LD ST, V0; JP 0x206
+        chip.load(&[0x60, 0x03, 0xF0, 0x15, 0xF0, 0x18, 0x12, 0x06]).unwrap();
+        for _ in 0..3 {
+            chip.cycle().unwrap();
+        }
+        assert_eq!((chip.delay_timer(), chip.sound_timer()), (3, 3));
+        chip.cycle().unwrap();
+        assert_eq!((chip.delay_timer(), chip.sound_timer()), (2, 2));
+        for _ in 0..8 {
+            chip.cycle().unwrap();
+        }
+        assert_eq!((chip.delay_timer(), chip.sound_timer()), (0, 0));
+    }
+
+    #[test]
+    fn key_wait_stores_the_key_once_released() {
+        let headless = Headless::new();
+        let mut chip = headless.chip();
+        chip.set_instructions_per_frame(1);
+        // LD V3, K; JP 0x202
+        chip.load(&[0xF3, 0x0A, 0x12, 0x02]).unwrap();
+        assert_eq!(chip.cycle().unwrap(), StepOutcome::Executed);
+        assert_eq!(chip.cycle().unwrap(), StepOutcome::WaitingForKey);
+        headless.press(0x7);
+        assert_eq!(chip.cycle().unwrap(), StepOutcome::WaitingForKey);
+        headless.release(0x7);
+        assert_eq!(chip.cycle().unwrap(), StepOutcome::Executed);
+        assert_eq!(chip.registers[3], 0x7);
+        assert_eq!(chip.pc, 0x202);
+    }
+
+    #[test]
+    fn key_wait_can_trigger_on_press() {
+        let headless = Headless::new();
+        let mut chip = headless.chip();
+        chip.set_instructions_per_frame(1);
+        let mut quirks = chip.quirks();
+        quirks.key_wait_on_press = true;
+        chip.set_quirks(quirks);
+        chip.load(&[0xF3, 0x0A, 0x12, 0x02]).unwrap();
+        chip.cycle().unwrap();
+        headless.press(0xA);
+        assert_eq!(chip.cycle().unwrap(), StepOutcome::Executed);
+        assert_eq!(chip.registers[3], 0xA);
+    }
+
+    #[test]
+    fn timers_keep_running_while_waiting_for_a_key() {
+        let mut chip = Headless::new().chip();
+        chip.set_instructions_per_frame(1);
+        // LD V0, 5; LD DT, V0; LD V1, K
+        chip.load(&[0x60, 0x05, 0xF0, 0x15, 0xF1, 0x0A]).unwrap();
+        for _ in 0..7 {
+            chip.cycle().unwrap();
+        }
+        assert!(chip.is_waiting_for_key());
+        assert_eq!(chip.delay_timer(), 0);
+    }
+
+    #[test]
+    fn keys_are_polled_at_the_start_of_each_frame() {
+        let headless = Headless::new();
+        let mut chip = headless.chip();
+        chip.set_instructions_per_frame(2);
+        // SKP V0; JP 0x200; JP 0x204
+        chip.load(&[0xE0, 0x9E, 0x12, 0x00, 0x12, 0x04]).unwrap();
+        chip.cycle().unwrap();
+        assert_eq!(chip.pc, 0x202);
+        headless.press(0x0);
+        chip.cycle().unwrap();
+        assert!(!chip.keypad().is_down(0x0));
+        chip.cycle().unwrap();
+        assert!(chip.keypad().is_down(0x0));
+        assert_eq!(chip.pc, 0x204);
+    }
+
+    #[test]
+    fn random_bytes_come_from_the_source() {
+        let mut chip = Headless::new().chip();
+        chip.set_random_source(Box::new(crate::random::Scripted::new(&[0xF5, 0x3C])));
+        // RND V0, 0x0F; RND V1, 0xFF; RND V2, 0xFF
+        chip.load(&[0xC0, 0x0F, 0xC1, 0xFF, 0xC2, 0xFF]).unwrap();
+        for _ in 0..3 {
+            chip.cycle().unwrap();
+        }
+        assert_eq!(chip.registers()[..3], [0x05, 0x3C, 0xF5]);
+    }
+
+    fn chip() -> super::Chip {
+        super::Chip::from_rom(&[]).unwrap()
+    }
+
+    #[test]
+    fn call_pushes_and_ret_pops() {
+        let mut chip = chip();
+        chip.set_pc(0x204);
+        chip.execute(Call { location: 0x300 }).unwrap();
+        assert_eq!((chip.pc(), chip.stack()), (0x300, &[0x204][..]));
+        chip.execute(Ret).unwrap();
+        assert_eq!((chip.pc(), chip.stack()), (0x204, &[][..]));
+        assert!(chip.execute(Ret).is_err());
+    }
+
+    #[test]
+    fn the_stack_holds_16_addresses() {
+        let mut chip = chip();
+        assert!(!chip.set_stack(&[0x200; 17]));
+        assert!(chip.set_stack(&[0x200; 16]));
+        assert!(chip.execute(Call { location: 0x300 }).is_err());
+    }
+
+    #[test]
+    fn skips_step_over_one_instruction() {
+        let mut chip = chip();
+        chip.set_register(1, 0x2A);
+        chip.set_register(2, 0x2A);
+        chip.execute(SkipEqualRegisterBytes { register_index: 1, bytes: 0x2A }).unwrap();
+        chip.execute(SkipNotEqualRegisterBytes { register_index: 1, bytes: 0x2A }).unwrap();
+        chip.execute(SkipEqualRegisterRegister { register_x: 1, register_y: 2 }).unwrap();
+        chip.execute(SkipNotEqualRegisterRegister { register_x: 1, register_y: 2 }).unwrap();
+        assert_eq!(chip.pc(), 0x204);
+    }
+
+    #[test]
+    fn logic_resets_vf_only_with_the_quirk() {
+        let mut chip = chip();
+        chip.set_register(0, 0b1100);
+        chip.set_register(1, 0b1010);
+        chip.set_register(0xF, 1);
+        chip.execute(BitwiseOr { register_x: 0, register_y: 1 }).unwrap();
+        assert_eq!(chip.registers()[0], 0b1110);
+        assert_eq!(chip.registers()[0xF], 0);
+
+        chip.set_quirks(Quirks::modern());
+        chip.set_register(0xF, 1);
+        chip.execute(BitwiseAnd { register_x: 0, register_y: 1 }).unwrap();
+        chip.execute(BitwiseXor { register_x: 0, register_y: 1 }).unwrap();
+        assert_eq!(chip.registers()[0], 0b0000);
+        assert_eq!(chip.registers()[0xF], 1);
+    }
+
+    #[test]
+    fn jump_with_offset_follows_the_quirk() {
+        let mut chip = chip();
+        chip.set_register(0, 0x10);
+        chip.set_register(3, 0x20);
+        chip.execute(JumpToLocationPlusZeroRegister { address: 0x345 }).unwrap();
+        assert_eq!(chip.pc(), 0x355);
+        chip.set_quirks(Quirks::chip48());
+        chip.execute(JumpToLocationPlusZeroRegister { address: 0x345 }).unwrap();
+        assert_eq!(chip.pc(), 0x365);
+    }
+
+    #[test]
+    fn bcd_and_register_dumps_use_i() {
+        let mut chip = chip();
+        chip.set_i(0x300);
+        chip.set_register(5, 234);
+        chip.execute(StoreBCD { register: 5 }).unwrap();
+        assert_eq!(chip.memory().slice(0x300, 3).unwrap(), [2, 3, 4]);
+
+        chip.execute(LoadRegistersFromMemory { to_register: 2 }).unwrap();
+        assert_eq!(chip.registers()[..3], [2, 3, 4]);
+        assert_eq!(chip.i(), 0x303);
+
+        chip.set_quirks(Quirks::modern());
+        chip.set_i(0x310);
+        chip.execute(StoreRegistersToMemory { to_register: 1 }).unwrap();
+        assert_eq!(chip.memory().slice(0x310, 3).unwrap(), [2, 3, 0]);
+        assert_eq!(chip.i(), 0x310);
+    }
+
+    #[test]
+    fn drawing_twice_erases_and_reports_a_collision() {
+        let mut chip = chip();
+        chip.set_register(0, 0xA);
+        chip.execute(SetIToLocationOfSprite { register: 0 }).unwrap();
+        let glyph = chip.memory().slice(chip.i() as usize, 5).unwrap().to_vec();
+        assert_eq!(glyph, [0xF0, 0x90, 0xF0, 0x90, 0x90]);
+
+        chip.execute(Display { register_x: 1, register_y: 2, nibble: 5 }).unwrap();
+        assert_eq!(chip.registers()[0xF], 0);
+        assert_eq!(chip.screen().get(0, 1), Some(true));
+        chip.execute(Display { register_x: 1, register_y: 2, nibble: 5 }).unwrap();
+        assert_eq!(chip.registers()[0xF], 1);
+        assert!(chip.screen().pixels().iter().all(|p| *p == 0));
+    }
+
+    #[test]
+    fn dxy0_in_low_resolution_depends_on_the_quirks() {
+        let lit = |quirks: Quirks, hires: bool| {
+            let mut chip = chip();
+            chip.set_quirks(quirks);
+            if hires {
+                chip.execute(HighResolution).unwrap();
+            }
+            for address in 0x300..0x320 {
+                chip.memory_mut().write(0xFF, address).unwrap();
+            }
+            chip.set_i(0x300);
+            chip.execute(DisplayLarge { register_x: 1, register_y: 2 }).unwrap();
+            chip.screen().pixels().iter().filter(|p| **p != 0).count()
+        };
+        assert_eq!(lit(Quirks::cosmac_vip(), false), 0);
+        assert_eq!(lit(Quirks::superchip(), false), 8 * 16);
+        assert_eq!(lit(Quirks::modern(), false), 16 * 16);
+        assert_eq!(lit(Quirks::superchip(), true), 16 * 16);
+        assert_eq!(lit(Quirks::cosmac_vip(), true), 16 * 16);
+    }
+
+    #[test]
+    fn run_until_stops_on_the_condition() {
+        // LD V0, 0; ADD V0, 1; JP 0x202
+        let mut chip = super::Chip::from_rom(&[0x60, 0x00, 0x70, 0x01, 0x12, 0x02]).unwrap();
+        let cycles = chip.run_until(100, |chip| chip.registers()[0] == 5).unwrap();
+        assert_eq!(cycles, 1 + 2 * 4 + 1);
+        chip.set_instructions_per_frame(10);
+        chip.run_frames(2).unwrap();
+        assert_eq!(chip.registers()[0], 15);
+        assert!(matches!(chip.run_until(100, |_| false), Err(crate::err::C8Err::CycleLimit { cycles: 100 })));
+        // EXIT
+        let mut chip = super::Chip::from_rom(&[0x00, 0xFD]).unwrap();
+        assert_eq!(chip.run_until(100, |_| false).unwrap(), 1);
+    }
+
+    /// Runs `instruction` on every pair of values of Vx and Vy, with x and y
+    /// being 1 and 2, then F and 2, then 1 and F.
+    fn exhaustive(instruction: impl Fn(u8, u8) -> crate::decoder::Instruction, expected: impl Fn(u8, u8) -> (u8, bool)) {
+        let mut chip = chip();
+        for (x, y) in [(1, 2), (0xF, 2), (1, 0xF)] {
+            for a in 0..=255 {
+                for b in 0..=255 {
+                    chip.set_register(x, a);
+                    chip.set_register(y, b);
+                    chip.execute(instruction(x, y)).unwrap();
+                    let (result, flag) = expected(a, b);
+                    let registers = chip.registers();
+                    assert_eq!(registers[0xF], flag as u8, "flag of V{x:X}={a:#04x}, V{y:X}={b:#04x}");
+                    if x != 0xF {
+                        assert_eq!(registers[x as usize], result, "V{x:X}={a:#04x}, V{y:X}={b:#04x}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn add_wraps_and_sets_the_carry() {
+        exhaustive(|x, y| AddRegisterToRegister { register_x: x, register_y: y }, |a, b| {
+            (a.wrapping_add(b), a as u16 + b as u16 > 0xFF)
+        });
+    }
+
+    #[test]
+    fn sub_wraps_and_sets_no_borrow() {
+        exhaustive(|x, y| SubtractRegisterToRegister { register_x: x, register_y: y }, |a, b| (a.wrapping_sub(b), a >= b));
+    }
+
+    #[test]
+    fn subn_wraps_and_sets_no_borrow() {
+        exhaustive(|x, y| SubtractInversed { register_x: x, register_y: y }, |a, b| (b.wrapping_sub(a), b >= a));
+    }
+
+    #[test]
+    fn shifts_set_the_bit_shifted_out() {
+        exhaustive(|x, y| LeastSignificantBit { register_x: x, register_y: y }, |_, b| (b >> 1, b & 1 == 1));
+        exhaustive(|x, y| MostSignificantBit { register_x: x, register_y: y }, |_, b| (b << 1, b & 0x80 != 0));
+    }
+
+    #[test]
+    fn add_byte_wraps_and_leaves_vf_alone() {
+        let mut chip = chip();
+        for a in 0..=255 {
+            for b in 0..=255 {
+                chip.set_register(3, a);
+                chip.set_register(0xF, 0x55);
+                chip.execute(AddBytesToRegister { register: 3, bytes: b }).unwrap();
+                assert_eq!(chip.registers()[3], a.wrapping_add(b));
+                assert_eq!(chip.registers()[0xF], 0x55);
+            }
+        }
+    }
 }
 
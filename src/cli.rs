@@ -0,0 +1,352 @@
+use std::str::FromStr;
+
+use minifb::Scale;
+
+use c8::audio::ToneConfig;
+use c8::io::Palette;
+use c8::quirks::{Preset, Quirks};
+
+pub const USAGE: &str = "\
+Usage: c8 [OPTIONS] <ROM>
+
+Options:
+    -c, --clock <HZ>        Instructions executed per second, rounded to whole frames
+        --ipf <N>           Instructions executed per 60 Hz frame [default: 15 for vip,
+                            30 for chip48 and schip, 1000 for modern]
+        --fast-forward <N>  Speed while Tab is held, F10 pauses [default: 4]
+    -s, --scale <N>         Window scale: 1, 2, 4, 8, 16 or 32 [default: 16]
+    -p, --palette <FG,BG[,P2,BOTH]>
+                            Pixel colours as hex RGB, e.g. ff0000,000000. XO-CHIP
+                            programs also use the second plane and both planes colours
+    -k, --keymap <LAYOUT|FILE>
+                            Keyboard layout: qwerty, azerty or dvorak, or a keymap
+                            file with lines like `A = Z, NumPad0` [default: qwerty]
+    -q, --quirks <PROFILE>  Quirk profile: vip, chip48, schip or modern [default: vip]
+        --quirk <NAME=on|off>
+                            Override a single quirk of the profile: shift, load-store,
+                            jump, vf-reset, wrap, key-press, dxy0 or dxy0-narrow.
+                            May be repeated
+        --tone <HZ>         Frequency of the sound timer tone [default: 440]
+        --volume <PERCENT>  Volume of the tone, from 0 to 100 [default: 25]
+        --waveform <SHAPE>  Tone shape: square, triangle, sine or sawtooth [default: square]
+        --mute              Play no sound
+        --rewind-depth <FRAMES>
+                            Frames kept to rewind with Backspace, 0 to disable [default: 600]
+        --rewind-budget <MIB>
+                            Memory the rewind history may use [default: 16]
+    -d, --debug             Start paused in the debugger, reading commands from stdin
+        --trace <FILE>      Write the PC, opcode, mnemonic, I and registers of every
+                            executed instruction to FILE
+        --record <FILE>     Record the keys pressed at each frame to a movie file
+        --replay <FILE>     Replay a movie with the quirks and clock it was recorded with
+        --verify            Stop with an error as soon as the replayed screen differs
+                            from the recorded one
+        --headless          Run without opening a window
+        --frames <N>        Stop a headless run after N frames of 1/60 s, or at the
+                            end of the movie when replaying
+        --dump <FILE>       Write the final screen of a headless run to FILE
+        --wav <FILE>        Record the sound of a headless run to a WAV file
+    -h, --help              Print this help";
+
+/// Errors produced while parsing the command line.
+#[derive(Debug, PartialEq)]
+pub enum CliErr {
+    Help,
+    MissingRom,
+    UnexpectedArgument(String),
+    UnknownOption(String),
+    HeadlessOnly(String),
+    NotHeadless(String),
+    Conflict(String, String),
+    ReplayOnly(String),
+    MissingFrames,
+    MissingValue(String),
+    InvalidValue { option: String, value: String },
+}
+
+impl CliErr {
+    pub fn message(&self) -> String {
+        match self {
+            CliErr::Help => USAGE.to_string(),
+            CliErr::MissingRom => "no ROM file given".to_string(),
+            CliErr::UnexpectedArgument(arg) => format!("unexpected argument `{arg}`"),
+            CliErr::UnknownOption(opt) => format!("unknown option `{opt}`"),
+            CliErr::HeadlessOnly(opt) => format!("option `{opt}` needs `--headless`"),
+            CliErr::NotHeadless(opt) => format!("option `{opt}` cannot be used with `--headless`"),
+            CliErr::Conflict(a, b) => format!("options `{a}` and `{b}` cannot be used together"),
+            CliErr::ReplayOnly(opt) => format!("option `{opt}` needs `--replay`"),
+            CliErr::MissingFrames => "`--headless` needs `--frames`".to_string(),
+            CliErr::MissingValue(opt) => format!("option `{opt}` needs a value"),
+            CliErr::InvalidValue { option, value } => format!("invalid value `{value}` for `{option}`"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Args {
+    pub rom: String,
+    /// Instructions per frame.
+    pub per_frame: u32,
+    pub fast_forward: u32,
+    pub scale: Scale,
+    pub palette: Palette,
+    pub quirks: Quirks,
+    pub keymap: Option<String>,
+    pub tone: ToneConfig,
+    pub mute: bool,
+    pub debug: bool,
+    pub trace: Option<String>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub verify: bool,
+    pub rewind_depth: usize,
+    /// In bytes.
+    pub rewind_budget: usize,
+    pub headless: bool,
+    pub frames: Option<u64>,
+    pub dump: Option<String>,
+    pub wav: Option<String>,
+}
+
+impl Args {
+    /// Parses the arguments following the program name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Args, CliErr> {
+        let mut rom = None;
+        let mut preset = Preset::CosmacVip;
+        let mut overrides = Vec::new();
+        let mut parsed = Args {
+            rom: String::new(),
+            per_frame: 0,
+            fast_forward: 4,
+            scale: Scale::X16,
+            palette: Palette::default(),
+            quirks: Quirks::default(),
+            keymap: None,
+            tone: ToneConfig::default(),
+            mute: false,
+            debug: false,
+            trace: None,
+            record: None,
+            replay: None,
+            verify: false,
+            rewind_depth: 600,
+            rewind_budget: 16 << 20,
+            headless: false,
+            frames: None,
+            dump: None,
+            wav: None,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(CliErr::Help),
+                "--headless" => parsed.headless = true,
+                "-d" | "--debug" => parsed.debug = true,
+                "--trace" => parsed.trace = Some(value(&arg, args.next())?),
+                "--record" => parsed.record = Some(value(&arg, args.next())?),
+                "--replay" => parsed.replay = Some(value(&arg, args.next())?),
+                "--verify" => parsed.verify = true,
+                "--rewind-depth" => parsed.rewind_depth = value(&arg, args.next())?,
+                "--rewind-budget" => parsed.rewind_budget = value::<usize>(&arg, args.next())?.saturating_mul(1 << 20),
+                "--frames" => parsed.frames = Some(value(&arg, args.next())?),
+                "--dump" => parsed.dump = Some(value(&arg, args.next())?),
+                "--wav" => parsed.wav = Some(value(&arg, args.next())?),
+                "--mute" => parsed.mute = true,
+                "--waveform" => parsed.tone.waveform = value(&arg, args.next())?,
+                "--tone" => {
+                    let raw: String = value(&arg, args.next())?;
+                    match raw.parse::<f64>() {
+                        Ok(frequency) if frequency > 0.0 && frequency < 20000.0 => parsed.tone.frequency = frequency,
+                        _ => return Err(CliErr::InvalidValue { option: arg, value: raw }),
+                    }
+                },
+                "--volume" => {
+                    let percent: u32 = value(&arg, args.next())?;
+                    if percent > 100 {
+                        return Err(CliErr::InvalidValue { option: arg, value: percent.to_string() });
+                    }
+                    parsed.tone.volume = percent as f64 / 100.0;
+                },
+                "-c" | "--clock" => parsed.per_frame = positive(&arg, args.next())?.div_ceil(60),
+                "--ipf" => parsed.per_frame = positive(&arg, args.next())?,
+                "--fast-forward" => parsed.fast_forward = positive(&arg, args.next())?,
+                "-s" | "--scale" => parsed.scale = parse_scale(&arg, args.next())?,
+                "-p" | "--palette" => parsed.palette = value(&arg, args.next())?,
+                "-k" | "--keymap" => parsed.keymap = Some(value(&arg, args.next())?),
+                "-q" | "--quirks" => preset = value(&arg, args.next())?,
+                "--quirk" => overrides.push(parse_quirk(&arg, args.next())?),
+                opt if opt.starts_with('-') && opt.len() > 1 => return Err(CliErr::UnknownOption(arg)),
+                _ => match rom {
+                    None => rom = Some(arg),
+                    Some(_) => return Err(CliErr::UnexpectedArgument(arg)),
+                },
+            }
+        }
+
+        if !parsed.headless {
+            if parsed.frames.is_some() {
+                return Err(CliErr::HeadlessOnly("--frames".to_string()));
+            }
+            if parsed.dump.is_some() {
+                return Err(CliErr::HeadlessOnly("--dump".to_string()));
+            }
+            if parsed.wav.is_some() {
+                return Err(CliErr::HeadlessOnly("--wav".to_string()));
+            }
+        } else if parsed.frames.is_none() && parsed.replay.is_none() {
+            return Err(CliErr::MissingFrames);
+        } else if parsed.debug {
+            return Err(CliErr::NotHeadless("--debug".to_string()));
+        } else if parsed.record.is_some() {
+            return Err(CliErr::NotHeadless("--record".to_string()));
+        }
+        if parsed.verify && parsed.replay.is_none() {
+            return Err(CliErr::ReplayOnly("--verify".to_string()));
+        }
+        if parsed.record.is_some() && parsed.replay.is_some() {
+            return Err(CliErr::Conflict("--record".to_string(), "--replay".to_string()));
+        }
+        if parsed.debug && (parsed.record.is_some() || parsed.replay.is_some()) {
+            let movie = if parsed.record.is_some() { "--record" } else { "--replay" };
+            return Err(CliErr::Conflict("--debug".to_string(), movie.to_string()));
+        }
+
+        parsed.quirks = Quirks::from(preset);
+        if parsed.per_frame == 0 {
+            parsed.per_frame = preset.instructions_per_frame();
+        }
+        for (name, on) in overrides {
+            parsed.quirks.set(&name, on);
+        }
+
+        parsed.rom = rom.ok_or(CliErr::MissingRom)?;
+        Ok(parsed)
+    }
+}
+
+fn value<T: FromStr>(option: &str, value: Option<String>) -> Result<T, CliErr> {
+    let value = value.ok_or_else(|| CliErr::MissingValue(option.to_string()))?;
+    value.parse().map_err(|_| CliErr::InvalidValue { option: option.to_string(), value })
+}
+
+fn positive(option: &str, raw: Option<String>) -> Result<u32, CliErr> {
+    match value(option, raw)? {
+        0 => Err(CliErr::InvalidValue { option: option.to_string(), value: "0".to_string() }),
+        n => Ok(n),
+    }
+}
+
+/// Parses `name=on` or `name=off`, checking that the quirk exists.
+fn parse_quirk(option: &str, raw: Option<String>) -> Result<(String, bool), CliErr> {
+    let raw: String = value(option, raw)?;
+    let invalid = || CliErr::InvalidValue { option: option.to_string(), value: raw.clone() };
+    let (name, on) = raw.split_once('=').ok_or_else(invalid)?;
+    let on = match on {
+        "on" | "true" | "1" => true,
+        "off" | "false" | "0" => false,
+        _ => return Err(invalid()),
+    };
+    if !Quirks::NAMES.contains(&name) {
+        return Err(invalid());
+    }
+    Ok((name.to_string(), on))
+}
+
+fn parse_scale(option: &str, raw: Option<String>) -> Result<Scale, CliErr> {
+    let factor: u32 = value(option, raw)?;
+    match factor {
+        1 => Ok(Scale::X1),
+        2 => Ok(Scale::X2),
+        4 => Ok(Scale::X4),
+        8 => Ok(Scale::X8),
+        16 => Ok(Scale::X16),
+        32 => Ok(Scale::X32),
+        _ => Err(CliErr::InvalidValue { option: option.to_string(), value: factor.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Args, CliErr> {
+        Args::parse(args.split_whitespace().map(str::to_string))
+    }
+
+    fn err(args: &str) -> CliErr {
+        parse(args).unwrap_err()
+    }
+
+    #[test]
+    fn defaults_follow_the_vip() {
+        let args = parse("game.ch8").unwrap();
+        assert_eq!(args.rom, "game.ch8");
+        assert_eq!(args.per_frame, 15);
+        assert_eq!(args.fast_forward, 4);
+        assert_eq!(args.quirks, Quirks::cosmac_vip());
+        assert_eq!((args.rewind_depth, args.rewind_budget), (600, 16 << 20));
+        assert!(!args.headless && !args.debug && !args.mute && !args.verify);
+        assert!(args.keymap.is_none() && args.record.is_none() && args.replay.is_none() && args.frames.is_none());
+    }
+
+    #[test]
+    fn the_preset_sets_the_speed_unless_given() {
+        assert_eq!(parse("-q schip game.ch8").unwrap().per_frame, 30);
+        assert_eq!(parse("--ipf 7 -q modern game.ch8").unwrap().per_frame, 7);
+        // Rounded up to whole frames.
+        assert_eq!(parse("--clock 700 game.ch8").unwrap().per_frame, 12);
+        assert_eq!(parse("-c 60 game.ch8").unwrap().per_frame, 1);
+    }
+
+    #[test]
+    fn quirk_overrides_apply_on_top_of_the_preset() {
+        let args = parse("--quirk wrap=on -q vip --quirk shift=off game.ch8").unwrap();
+        let expected = Quirks { wrap_sprites: true, shift_uses_vy: false, ..Quirks::cosmac_vip() };
+        assert_eq!(args.quirks, expected);
+        assert_eq!(err("--quirk nope=on game.ch8"), CliErr::InvalidValue { option: "--quirk".to_string(), value: "nope=on".to_string() });
+        assert_eq!(err("--quirk wrap=maybe game.ch8"), CliErr::InvalidValue { option: "--quirk".to_string(), value: "wrap=maybe".to_string() });
+        assert_eq!(err("--quirk wrap game.ch8"), CliErr::InvalidValue { option: "--quirk".to_string(), value: "wrap".to_string() });
+    }
+
+    #[test]
+    fn headless_runs() {
+        let args = parse("--headless --frames 120 --dump out.txt --wav out.wav game.ch8").unwrap();
+        assert!(args.headless);
+        assert_eq!(args.frames, Some(120));
+        assert_eq!(args.dump.as_deref(), Some("out.txt"));
+        assert_eq!(args.wav.as_deref(), Some("out.wav"));
+        assert!(parse("--headless --replay run.c8mv game.ch8").is_ok());
+        assert_eq!(err("--headless game.ch8"), CliErr::MissingFrames);
+        assert_eq!(err("--frames 10 game.ch8"), CliErr::HeadlessOnly("--frames".to_string()));
+        assert_eq!(err("--dump out.txt game.ch8"), CliErr::HeadlessOnly("--dump".to_string()));
+        assert_eq!(err("--wav out.wav game.ch8"), CliErr::HeadlessOnly("--wav".to_string()));
+        assert_eq!(err("--headless --frames 1 --debug game.ch8"), CliErr::NotHeadless("--debug".to_string()));
+        assert_eq!(err("--headless --frames 1 --record run.c8mv game.ch8"), CliErr::NotHeadless("--record".to_string()));
+    }
+
+    #[test]
+    fn movie_options_conflict() {
+        assert!(parse("--replay run.c8mv --verify game.ch8").unwrap().verify);
+        assert_eq!(err("--verify game.ch8"), CliErr::ReplayOnly("--verify".to_string()));
+        assert_eq!(err("--record a --replay b game.ch8"), CliErr::Conflict("--record".to_string(), "--replay".to_string()));
+        assert_eq!(err("-d --record a game.ch8"), CliErr::Conflict("--debug".to_string(), "--record".to_string()));
+        assert_eq!(err("-d --replay a game.ch8"), CliErr::Conflict("--debug".to_string(), "--replay".to_string()));
+    }
+
+    #[test]
+    fn bad_arguments() {
+        assert_eq!(err("-h game.ch8"), CliErr::Help);
+        assert_eq!(err(""), CliErr::MissingRom);
+        assert_eq!(err("a.ch8 b.ch8"), CliErr::UnexpectedArgument("b.ch8".to_string()));
+        assert_eq!(err("--nope game.ch8"), CliErr::UnknownOption("--nope".to_string()));
+        assert_eq!(err("game.ch8 --ipf"), CliErr::MissingValue("--ipf".to_string()));
+        let invalid = |option: &str, value: &str| CliErr::InvalidValue { option: option.to_string(), value: value.to_string() };
+        assert_eq!(err("--ipf 0 game.ch8"), invalid("--ipf", "0"));
+        assert_eq!(err("--clock fast game.ch8"), invalid("--clock", "fast"));
+        assert_eq!(err("-s 3 game.ch8"), invalid("-s", "3"));
+        assert_eq!(err("--volume 101 game.ch8"), invalid("--volume", "101"));
+        assert_eq!(err("--tone 0 game.ch8"), invalid("--tone", "0"));
+        assert_eq!(err("-q pdp8 game.ch8"), invalid("-q", "pdp8"));
+    }
+}
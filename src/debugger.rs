@@ -0,0 +1,345 @@
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use c8::Chip;
+use c8::c8::StepOutcome;
+use c8::disasm::{Decoded, Syntax};
+use c8::types::{AddressLong, Data};
+
+pub const HELP: &str = "\
+Commands:
+    break [ADDR]        Stop before executing ADDR, or list the breakpoints
+    delete ADDR         Remove the breakpoint at ADDR
+    step [N]            Execute N instructions [default: 1]
+    continue            Run until a breakpoint is reached
+    regs                Print the registers and the timers
+    mem ADDR LEN        Print LEN bytes of memory starting at ADDR
+    stack               Print the return addresses
+    set REG VALUE       Change V0 to VF, I or PC
+    disasm [ADDR] [N]   Decode N instructions starting at ADDR [default: PC, 10]
+    help                Print this help
+    quit                Stop the program
+Numbers are decimal unless they start with 0x.";
+
+/// A register the debugger can change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    V(Data),
+    I,
+    Pc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Break(Option<AddressLong>),
+    Delete(AddressLong),
+    Step(u32),
+    Continue,
+    Regs,
+    Mem { address: AddressLong, len: usize },
+    Stack,
+    Set { target: Target, value: u16 },
+    Disasm { address: Option<AddressLong>, count: usize },
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let command = match words[..] {
+            ["break" | "b"] => Command::Break(None),
+            ["break" | "b", address] => Command::Break(Some(number(address)?)),
+            ["delete" | "d", address] => Command::Delete(number(address)?),
+            ["step" | "s"] => Command::Step(1),
+            ["step" | "s", n] => Command::Step(number(n)?),
+            ["continue" | "c"] => Command::Continue,
+            ["regs" | "r"] => Command::Regs,
+            ["mem" | "m", address, len] => Command::Mem { address: number(address)?, len: number(len)? },
+            ["stack"] => Command::Stack,
+            ["set", target, value] => Command::Set { target: target.parse()?, value: number(value)? },
+            ["disasm"] => Command::Disasm { address: None, count: 10 },
+            ["disasm", address] => Command::Disasm { address: Some(number(address)?), count: 10 },
+            ["disasm", address, count] => Command::Disasm { address: Some(number(address)?), count: number(count)? },
+            ["help" | "h"] => Command::Help,
+            ["quit" | "q"] => Command::Quit,
+            [] => return Err("no command given".to_string()),
+            [name, ..] => return Err(format!("unknown command or wrong arguments for `{name}`, see `help`")),
+        };
+        Ok(command)
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "I" => Ok(Target::I),
+            "PC" => Ok(Target::Pc),
+            name => name
+                .strip_prefix('V')
+                .filter(|x| x.len() == 1)
+                .and_then(|x| Data::from_str_radix(x, 16).ok())
+                .map(Target::V)
+                .ok_or_else(|| format!("unknown register `{s}`")),
+        }
+    }
+}
+
+fn number<T: TryFrom<u64>>(s: &str) -> Result<T, String> {
+    let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    value.ok().and_then(|v| T::try_from(v).ok()).ok_or_else(|| format!("invalid number `{s}`"))
+}
+
+/// Why the debugger got back to the prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// The requested instructions have been executed.
+    Done,
+    Breakpoint,
+    /// The program has exited or crashed.
+    Ended,
+    /// The host asked to stop, e.g. the window has been closed.
+    Closed,
+}
+
+/// A command line debugger driving a `Chip` until the user quits.
+#[derive(Debug, Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<AddressLong>,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger::default()
+    }
+
+    /// Reads commands from stdin until `quit` or the end of the input. `tick` is
+    /// called after every instruction and returns `false` to end the session.
+    pub fn run<F: FnMut(&mut Chip) -> bool>(&mut self, chip: &mut Chip, mut tick: F) {
+        println!("Paused before the first instruction, type `help` for the commands.");
+        print_next(chip);
+        let stdin = io::stdin();
+        loop {
+            print!("(c8) ");
+            io::stdout().flush().ok();
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                return;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.parse() {
+                Ok(Command::Quit) => return,
+                Ok(command) => {
+                    if self.execute(command, chip, &mut tick) == Stop::Closed {
+                        return;
+                    }
+                },
+                Err(e) => println!("{e}"),
+            }
+        }
+    }
+
+    pub fn execute<F: FnMut(&mut Chip) -> bool>(&mut self, command: Command, chip: &mut Chip, tick: &mut F) -> Stop {
+        match command {
+            Command::Break(Some(address)) => {
+                self.breakpoints.insert(address);
+            },
+            Command::Break(None) => {
+                for address in &self.breakpoints {
+                    println!("{address:#06x}");
+                }
+            },
+            Command::Delete(address) => {
+                if !self.breakpoints.remove(&address) {
+                    println!("no breakpoint at {address:#06x}");
+                }
+            },
+            Command::Step(n) => {
+                let stop = self.step(chip, n, tick);
+                print_next(chip);
+                return stop;
+            },
+            Command::Continue => {
+                let stop = self.resume(chip, tick);
+                if stop == Stop::Breakpoint {
+                    println!("breakpoint reached");
+                }
+                print_next(chip);
+                return stop;
+            },
+            Command::Regs => print_registers(chip),
+            Command::Mem { address, len } => match chip.memory().slice(address as usize, len) {
+                Ok(bytes) => {
+                    for (row, chunk) in bytes.chunks(16).enumerate() {
+                        let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+                        println!("{:#06x}: {}", address as usize + 16 * row, hex.join(" "));
+                    }
+                },
+                Err(e) => println!("{e}"),
+            },
+            Command::Stack => {
+                for (depth, address) in chip.stack().iter().enumerate().rev() {
+                    println!("{depth:2}: {address:#06x}");
+                }
+            },
+            Command::Set { target, value } => match (target, Data::try_from(value)) {
+                (Target::V(x), Ok(value)) => {
+                    chip.set_register(x, value);
+                },
+                (Target::V(_), Err(_)) => println!("registers hold a single byte"),
+                (Target::I, _) => chip.set_i(value),
+                (Target::Pc, _) => chip.set_pc(value),
+            },
+            Command::Disasm { address, count } => {
+                for line in listing(chip, address.unwrap_or(chip.pc()), count) {
+                    println!("{line}");
+                }
+            },
+            Command::Help => println!("{HELP}"),
+            Command::Quit => return Stop::Closed,
+        }
+        Stop::Done
+    }
+
+    /// Executes `n` instructions, stopping early at a breakpoint.
+    fn step<F: FnMut(&mut Chip) -> bool>(&self, chip: &mut Chip, n: u32, tick: &mut F) -> Stop {
+        for executed in 0..n {
+            if executed > 0 && self.breakpoints.contains(&chip.pc()) {
+                return Stop::Breakpoint;
+            }
+            let stop = advance(chip, tick);
+            if stop != Stop::Done {
+                return stop;
+            }
+        }
+        Stop::Done
+    }
+
+    /// Executes instructions until the next breakpoint, leaving the current one.
+    pub fn resume<F: FnMut(&mut Chip) -> bool>(&self, chip: &mut Chip, tick: &mut F) -> Stop {
+        loop {
+            let stop = advance(chip, tick);
+            if stop != Stop::Done {
+                return stop;
+            }
+            if self.breakpoints.contains(&chip.pc()) {
+                return Stop::Breakpoint;
+            }
+        }
+    }
+}
+
+fn advance<F: FnMut(&mut Chip) -> bool>(chip: &mut Chip, tick: &mut F) -> Stop {
+    match chip.cycle() {
+        Ok(StepOutcome::Halted) => {
+            println!("the program has exited");
+            return Stop::Ended;
+        },
+        Ok(_) => {},
+        Err(e) => {
+            println!("the program crashed: {e}");
+            return Stop::Ended;
+        },
+    }
+    if tick(chip) { Stop::Done } else { Stop::Closed }
+}
+
+/// The instruction at `address` and its size, or `None` past the end of the memory.
+fn disassemble(chip: &Chip, address: AddressLong) -> Option<(String, usize)> {
+    let decoded = Decoded::at(&chip.memory().vector, address as usize)?;
+    let marker = if address == chip.pc() { ">" } else { " " };
+    let line = format!("{marker} {address:#06x}  {:<9}  {}", decoded.raw(), decoded.mnemonic(Syntax::Cowgod));
+    Some((line, decoded.size()))
+}
+
+/// Up to `count` instructions from `address`, each one starting after the previous one.
+fn listing(chip: &Chip, mut address: AddressLong, count: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for _ in 0..count {
+        let Some((line, size)) = disassemble(chip, address) else { break };
+        lines.push(line);
+        address = address.wrapping_add(size as AddressLong);
+    }
+    lines
+}
+
+fn print_next(chip: &Chip) {
+    if chip.is_waiting_for_key() {
+        println!("waiting for a key");
+    }
+    if let Some((line, _)) = disassemble(chip, chip.pc()) {
+        println!("{line}");
+    }
+}
+
+fn print_registers(chip: &Chip) {
+    for (row, registers) in chip.registers().chunks(8).enumerate() {
+        let values: Vec<String> = registers
+            .iter()
+            .enumerate()
+            .map(|(x, value)| format!("V{:X}={value:02x}", 8 * row + x))
+            .collect();
+        println!("{}", values.join(" "));
+    }
+    println!(
+        "PC={:#06x} I={:#06x} SP={} DT={} ST={}",
+        chip.pc(),
+        chip.i(),
+        chip.stack().len(),
+        chip.delay_timer(),
+        chip.sound_timer()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use c8::headless::Headless;
+
+    use super::*;
+
+    #[test]
+    fn commands_accept_hex_and_decimal_numbers() {
+        assert_eq!("break 0x20A".parse(), Ok(Command::Break(Some(0x20A))));
+        assert_eq!("step 16".parse(), Ok(Command::Step(16)));
+        assert_eq!("set V3 0x10".parse(), Ok(Command::Set { target: Target::V(3), value: 0x10 }));
+        assert_eq!("mem 0x200 4".parse(), Ok(Command::Mem { address: 0x200, len: 4 }));
+        assert!("set VG 1".parse::<Command>().is_err());
+        assert!("break 0x10000".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn continue_stops_at_the_next_breakpoint() {
+        let mut chip = Headless::new().chip();
+        // LD V0, 1; ADD V0, 1; JP 0x202
+        chip.load(&[0x60, 0x01, 0x70, 0x01, 0x12, 0x02]).unwrap();
+        let mut debugger = Debugger::new();
+        let mut tick = |_: &mut Chip| true;
+        debugger.execute(Command::Break(Some(0x202)), &mut chip, &mut tick);
+
+        assert_eq!(debugger.resume(&mut chip, &mut tick), Stop::Breakpoint);
+        assert_eq!((chip.pc(), chip.registers()[0]), (0x202, 1));
+        assert_eq!(debugger.resume(&mut chip, &mut tick), Stop::Breakpoint);
+        assert_eq!((chip.pc(), chip.registers()[0]), (0x202, 2));
+    }
+
+    #[test]
+    fn disasm_steps_over_the_word_of_long_i() {
+        let mut chip = Headless::new().chip();
+        // LD I, LONG 0x1234; CLS
+        chip.load(&[0xF0, 0x00, 0x12, 0x34, 0x00, 0xE0]).unwrap();
+        let lines = listing(&chip, 0x200, 2);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("> 0x0200  F000 1234"), "{}", lines[0]);
+        assert!(lines[1].starts_with("  0x0204  00E0"), "{}", lines[1]);
+    }
+}
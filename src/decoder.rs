@@ -1,7 +1,11 @@
+use std::fmt;
+use std::num::IntErrorKind;
+use std::str::FromStr;
+
 use crate::types::AddressLong;
 use crate::types::Data;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     /// 00E0 - CLS
     Cls,
@@ -9,6 +13,27 @@ pub enum Instruction {
     /// 00EE - RET
     Ret,
 
+    /// 00Cn - SCD nibble (SUPER-CHIP)
+    ScrollDown { rows: Data },
+
+    /// 00Dn - SCU nibble (XO-CHIP)
+    ScrollUp { rows: Data },
+
+    /// 00FB - SCR (SUPER-CHIP)
+    ScrollRight,
+
+    /// 00FC - SCL (SUPER-CHIP)
+    ScrollLeft,
+
+    /// 00FD - EXIT (SUPER-CHIP)
+    Exit,
+
+    /// 00FE - LOW (SUPER-CHIP)
+    LowResolution,
+
+    /// 00FF - HIGH (SUPER-CHIP)
+    HighResolution,
+
     /// 1nnn - JP addr
     Jump { location: AddressLong },
 
@@ -24,6 +49,13 @@ pub enum Instruction {
     /// 5xy0 - SE Vx, Vy
     SkipEqualRegisterRegister {register_x: Data, register_y: Data },
 
+    /// 5xy2 - LD [I], Vx - Vy (XO-CHIP)
+    /// Stores the registers from Vx to Vy, in that order, starting at I.
+    StoreRegisterRange { register_x: Data, register_y: Data },
+
+    /// 5xy3 - LD Vx - Vy, [I] (XO-CHIP)
+    LoadRegisterRange { register_x: Data, register_y: Data },
+
     /// 6xkk - LD Vx, byte
     SetRegisterToBytes { register: Data, bytes: Data },
 
@@ -49,14 +81,14 @@ pub enum Instruction {
     SubtractRegisterToRegister { register_x: Data, register_y: Data },
 
     /// 8xy6 - SHR Vx {, Vy}
-    /// Set Vx = Vx SHR 1.
-    LeastSignificantBit { register: Data },
+    /// Set Vx = Vx SHR 1, or Vy SHR 1 with the `shift_uses_vy` quirk.
+    LeastSignificantBit { register_x: Data, register_y: Data },
 
     /// 8xy7 - SUBN Vx, Vy
     SubtractInversed { register_x: Data, register_y: Data },
 
     /// 8xyE - SHL Vx {, Vy}
-    MostSignificantBit { register: Data },
+    MostSignificantBit { register_x: Data, register_y: Data },
 
     /// 9xy0 - SNE Vx, Vy
     SkipNotEqualRegisterRegister { register_x: Data, register_y: Data },
@@ -73,12 +105,27 @@ pub enum Instruction {
     /// Dxyn - DRW Vx, Vy, nibble
     Display { register_x: Data, register_y: Data, nibble: Data },
 
+    /// Dxy0 - DRW Vx, Vy, 0 (SUPER-CHIP)
+    /// Draws a 16x16 sprite.
+    DisplayLarge { register_x: Data, register_y: Data },
+
     /// Ex9E - SKP Vx
     SkipIfKeyIsPressed { register: Data },
 
     /// ExA1 - SKNP Vx
     SkipIfKeyIsNotPressed { register: Data },
 
+    /// F000 nnnn - LD I, long addr (XO-CHIP)
+    /// The address is the word following the instruction.
+    SetILong,
+
+    /// Fn01 - PLANE n (XO-CHIP)
+    SelectPlanes { planes: Data },
+
+    /// F002 - AUDIO (XO-CHIP)
+    /// Loads the 16 bytes at I into the audio pattern buffer.
+    LoadAudioPattern,
+
     /// Fx07 - LD Vx, DT
     SetRegisterToDelayTimer { register: Data },
 
@@ -97,6 +144,9 @@ pub enum Instruction {
     /// Fx29 - LD F, Vx
     SetIToLocationOfSprite { register: Data },
 
+    /// Fx30 - LD HF, Vx (SUPER-CHIP)
+    SetIToLocationOfLargeSprite { register: Data },
+
     /// Fx33 - LD B, Vx
     StoreBCD { register: Data },
     
@@ -106,7 +156,327 @@ pub enum Instruction {
     /// Fx65 - LD Vx, [I]
     LoadRegistersFromMemory { to_register: Data },
 
-    Invalid
+    /// Fx3A - PITCH Vx (XO-CHIP)
+    SetPitch { register: Data },
+
+    /// Fx75 - LD R, Vx (SUPER-CHIP)
+    StoreRegistersToFlags { to_register: Data },
+
+    /// Fx85 - LD Vx, R (SUPER-CHIP)
+    LoadRegistersFromFlags { to_register: Data },
+
+    /// Any word that is not one of the above.
+    Invalid { opcode: u16 },
+}
+
+impl Instruction {
+    /// The inverse of `decode`. F000 is encoded without the address that follows it.
+    pub fn encode(&self) -> u16 {
+        use Instruction::*;
+        let xy = |op: u16, x: Data, y: Data, n: u16| op | (x as u16) << 8 | (y as u16) << 4 | n;
+        let xkk = |op: u16, x: Data, kk: Data| op | (x as u16) << 8 | kk as u16;
+        match *self {
+            Cls => 0x00E0,
+            Ret => 0x00EE,
+            ScrollDown { rows } => 0x00C0 | rows as u16,
+            ScrollUp { rows } => 0x00D0 | rows as u16,
+            ScrollRight => 0x00FB,
+            ScrollLeft => 0x00FC,
+            Exit => 0x00FD,
+            LowResolution => 0x00FE,
+            HighResolution => 0x00FF,
+            Jump { location } => 0x1000 | location,
+            Call { location } => 0x2000 | location,
+            SkipEqualRegisterBytes { register_index, bytes } => xkk(0x3000, register_index, bytes),
+            SkipNotEqualRegisterBytes { register_index, bytes } => xkk(0x4000, register_index, bytes),
+            SkipEqualRegisterRegister { register_x, register_y } => xy(0x5000, register_x, register_y, 0x0),
+            StoreRegisterRange { register_x, register_y } => xy(0x5000, register_x, register_y, 0x2),
+            LoadRegisterRange { register_x, register_y } => xy(0x5000, register_x, register_y, 0x3),
+            SetRegisterToBytes { register, bytes } => xkk(0x6000, register, bytes),
+            AddBytesToRegister { register, bytes } => xkk(0x7000, register, bytes),
+            SetRegisterToRegister { register_x, register_y } => xy(0x8000, register_x, register_y, 0x0),
+            BitwiseOr { register_x, register_y } => xy(0x8000, register_x, register_y, 0x1),
+            BitwiseAnd { register_x, register_y } => xy(0x8000, register_x, register_y, 0x2),
+            BitwiseXor { register_x, register_y } => xy(0x8000, register_x, register_y, 0x3),
+            AddRegisterToRegister { register_x, register_y } => xy(0x8000, register_x, register_y, 0x4),
+            SubtractRegisterToRegister { register_x, register_y } => xy(0x8000, register_x, register_y, 0x5),
+            LeastSignificantBit { register_x, register_y } => xy(0x8000, register_x, register_y, 0x6),
+            SubtractInversed { register_x, register_y } => xy(0x8000, register_x, register_y, 0x7),
+            MostSignificantBit { register_x, register_y } => xy(0x8000, register_x, register_y, 0xE),
+            SkipNotEqualRegisterRegister { register_x, register_y } => xy(0x9000, register_x, register_y, 0x0),
+            SetI { value } => 0xA000 | value,
+            JumpToLocationPlusZeroRegister { address } => 0xB000 | address,
+            Random { register, value } => xkk(0xC000, register, value),
+            Display { register_x, register_y, nibble } => xy(0xD000, register_x, register_y, nibble as u16),
+            DisplayLarge { register_x, register_y } => xy(0xD000, register_x, register_y, 0x0),
+            SkipIfKeyIsPressed { register } => xkk(0xE000, register, 0x9E),
+            SkipIfKeyIsNotPressed { register } => xkk(0xE000, register, 0xA1),
+            SetILong => 0xF000,
+            SelectPlanes { planes } => xkk(0xF000, planes, 0x01),
+            LoadAudioPattern => 0xF002,
+            SetRegisterToDelayTimer { register } => xkk(0xF000, register, 0x07),
+            WaitForKey { register } => xkk(0xF000, register, 0x0A),
+            SetDelayTimer { register } => xkk(0xF000, register, 0x15),
+            SetSoundTimer { register } => xkk(0xF000, register, 0x18),
+            AddRegisterToI { register } => xkk(0xF000, register, 0x1E),
+            SetIToLocationOfSprite { register } => xkk(0xF000, register, 0x29),
+            SetIToLocationOfLargeSprite { register } => xkk(0xF000, register, 0x30),
+            StoreBCD { register } => xkk(0xF000, register, 0x33),
+            SetPitch { register } => xkk(0xF000, register, 0x3A),
+            StoreRegistersToMemory { to_register } => xkk(0xF000, to_register, 0x55),
+            LoadRegistersFromMemory { to_register } => xkk(0xF000, to_register, 0x65),
+            StoreRegistersToFlags { to_register } => xkk(0xF000, to_register, 0x75),
+            LoadRegistersFromFlags { to_register } => xkk(0xF000, to_register, 0x85),
+            Invalid { opcode } => opcode,
+        }
+    }
+}
+
+/// The Cowgod mnemonic given in the comment of each variant.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+        match *self {
+            Cls => f.write_str("CLS"),
+            Ret => f.write_str("RET"),
+            ScrollDown { rows } => write!(f, "SCD {rows}"),
+            ScrollUp { rows } => write!(f, "SCU {rows}"),
+            ScrollRight => f.write_str("SCR"),
+            ScrollLeft => f.write_str("SCL"),
+            Exit => f.write_str("EXIT"),
+            LowResolution => f.write_str("LOW"),
+            HighResolution => f.write_str("HIGH"),
+            Jump { location } => write!(f, "JP {location:#05x}"),
+            Call { location } => write!(f, "CALL {location:#05x}"),
+            SkipEqualRegisterBytes { register_index, bytes } => write!(f, "SE V{register_index:X}, {bytes:#04x}"),
+            SkipNotEqualRegisterBytes { register_index, bytes } => write!(f, "SNE V{register_index:X}, {bytes:#04x}"),
+            SkipEqualRegisterRegister { register_x, register_y } => write!(f, "SE V{register_x:X}, V{register_y:X}"),
+            StoreRegisterRange { register_x, register_y } => write!(f, "LD [I], V{register_x:X} - V{register_y:X}"),
+            LoadRegisterRange { register_x, register_y } => write!(f, "LD V{register_x:X} - V{register_y:X}, [I]"),
+            SetRegisterToBytes { register, bytes } => write!(f, "LD V{register:X}, {bytes:#04x}"),
+            AddBytesToRegister { register, bytes } => write!(f, "ADD V{register:X}, {bytes:#04x}"),
+            SetRegisterToRegister { register_x, register_y } => write!(f, "LD V{register_x:X}, V{register_y:X}"),
+            BitwiseOr { register_x, register_y } => write!(f, "OR V{register_x:X}, V{register_y:X}"),
+            BitwiseAnd { register_x, register_y } => write!(f, "AND V{register_x:X}, V{register_y:X}"),
+            BitwiseXor { register_x, register_y } => write!(f, "XOR V{register_x:X}, V{register_y:X}"),
+            AddRegisterToRegister { register_x, register_y } => write!(f, "ADD V{register_x:X}, V{register_y:X}"),
+            SubtractRegisterToRegister { register_x, register_y } => write!(f, "SUB V{register_x:X}, V{register_y:X}"),
+            LeastSignificantBit { register_x, register_y } => write!(f, "SHR V{register_x:X}, V{register_y:X}"),
+            SubtractInversed { register_x, register_y } => write!(f, "SUBN V{register_x:X}, V{register_y:X}"),
+            MostSignificantBit { register_x, register_y } => write!(f, "SHL V{register_x:X}, V{register_y:X}"),
+            SkipNotEqualRegisterRegister { register_x, register_y } => write!(f, "SNE V{register_x:X}, V{register_y:X}"),
+            SetI { value } => write!(f, "LD I, {value:#05x}"),
+            JumpToLocationPlusZeroRegister { address } => write!(f, "JP V0, {address:#05x}"),
+            Random { register, value } => write!(f, "RND V{register:X}, {value:#04x}"),
+            Display { register_x, register_y, nibble } => write!(f, "DRW V{register_x:X}, V{register_y:X}, {nibble}"),
+            DisplayLarge { register_x, register_y } => write!(f, "DRW V{register_x:X}, V{register_y:X}, 0"),
+            SkipIfKeyIsPressed { register } => write!(f, "SKP V{register:X}"),
+            SkipIfKeyIsNotPressed { register } => write!(f, "SKNP V{register:X}"),
+            SetILong => f.write_str("LD I, LONG"),
+            SelectPlanes { planes } => write!(f, "PLANE {planes}"),
+            LoadAudioPattern => f.write_str("AUDIO"),
+            SetRegisterToDelayTimer { register } => write!(f, "LD V{register:X}, DT"),
+            WaitForKey { register } => write!(f, "LD V{register:X}, K"),
+            SetDelayTimer { register } => write!(f, "LD DT, V{register:X}"),
+            SetSoundTimer { register } => write!(f, "LD ST, V{register:X}"),
+            AddRegisterToI { register } => write!(f, "ADD I, V{register:X}"),
+            SetIToLocationOfSprite { register } => write!(f, "LD F, V{register:X}"),
+            SetIToLocationOfLargeSprite { register } => write!(f, "LD HF, V{register:X}"),
+            StoreBCD { register } => write!(f, "LD B, V{register:X}"),
+            SetPitch { register } => write!(f, "PITCH V{register:X}"),
+            StoreRegistersToMemory { to_register } => write!(f, "LD [I], V{to_register:X}"),
+            LoadRegistersFromMemory { to_register } => write!(f, "LD V{to_register:X}, [I]"),
+            StoreRegistersToFlags { to_register } => write!(f, "LD R, V{to_register:X}"),
+            LoadRegistersFromFlags { to_register } => write!(f, "LD V{to_register:X}, R"),
+            Invalid { opcode } => write!(f, "DW {opcode:#06x}"),
+        }
+    }
+}
+
+/// Parses a single instruction written as `Display` does, numbers only.
+/// The address of `LD I, LONG addr` is not part of the instruction and is dropped.
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (word, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let value = |word: &str| number(word).unwrap_or_else(|| Err(format!("invalid value `{word}`")));
+        instruction(&word.to_ascii_uppercase(), &operands(rest.trim(), value)?, |value, bits| fit(*value, bits))
+    }
+}
+
+/// An operand in Cowgod's syntax, values being written as `T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Operand<T> {
+    V(Data),
+    /// Vx - Vy
+    Range(Data, Data),
+    I,
+    /// [I]
+    Indirect,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    R,
+    /// LONG addr, the address being optional when parsing a single instruction.
+    Long(Option<T>),
+    Value(T),
+}
+
+/// The instruction written `mnemonic operands`, `fit_value` checking that a
+/// value fits in a number of bits. The address following F000 is left to the caller.
+pub(crate) fn instruction<T>(mnemonic: &str, operands: &[Operand<T>], fit_value: impl Fn(&T, u32) -> Result<u16, String>) -> Result<Instruction, String> {
+    use Instruction::*;
+    use Operand::*;
+    let byte = |v: &T| fit_value(v, 8).map(|v| v as Data);
+    let nibble = |v: &T| fit_value(v, 4).map(|v| v as Data);
+    let address = |v: &T| fit_value(v, 12);
+
+    let instruction = match (mnemonic, operands) {
+        ("CLS", []) => Cls,
+        ("RET", []) => Ret,
+        ("SCD", [Value(n)]) => ScrollDown { rows: nibble(n)? },
+        ("SCU", [Value(n)]) => ScrollUp { rows: nibble(n)? },
+        ("SCR", []) => ScrollRight,
+        ("SCL", []) => ScrollLeft,
+        ("EXIT", []) => Exit,
+        ("LOW", []) => LowResolution,
+        ("HIGH", []) => HighResolution,
+        ("JP", [Value(a)]) => Jump { location: address(a)? },
+        ("JP", [V(0), Value(a)]) => JumpToLocationPlusZeroRegister { address: address(a)? },
+        ("CALL", [Value(a)]) => Call { location: address(a)? },
+        ("SE", [V(x), V(y)]) => SkipEqualRegisterRegister { register_x: *x, register_y: *y },
+        ("SE", [V(x), Value(b)]) => SkipEqualRegisterBytes { register_index: *x, bytes: byte(b)? },
+        ("SNE", [V(x), V(y)]) => SkipNotEqualRegisterRegister { register_x: *x, register_y: *y },
+        ("SNE", [V(x), Value(b)]) => SkipNotEqualRegisterBytes { register_index: *x, bytes: byte(b)? },
+        ("LD", [Indirect, Range(x, y)]) => StoreRegisterRange { register_x: *x, register_y: *y },
+        ("LD", [Range(x, y), Indirect]) => LoadRegisterRange { register_x: *x, register_y: *y },
+        ("LD", [V(x), V(y)]) => SetRegisterToRegister { register_x: *x, register_y: *y },
+        ("LD", [V(x), Dt]) => SetRegisterToDelayTimer { register: *x },
+        ("LD", [V(x), K]) => WaitForKey { register: *x },
+        ("LD", [V(x), Indirect]) => LoadRegistersFromMemory { to_register: *x },
+        ("LD", [V(x), R]) => LoadRegistersFromFlags { to_register: *x },
+        ("LD", [V(x), Value(b)]) => SetRegisterToBytes { register: *x, bytes: byte(b)? },
+        ("LD", [I, Long(_)]) => SetILong,
+        ("LD", [I, Value(a)]) => SetI { value: address(a)? },
+        ("LD", [Dt, V(x)]) => SetDelayTimer { register: *x },
+        ("LD", [St, V(x)]) => SetSoundTimer { register: *x },
+        ("LD", [F, V(x)]) => SetIToLocationOfSprite { register: *x },
+        ("LD", [Hf, V(x)]) => SetIToLocationOfLargeSprite { register: *x },
+        ("LD", [B, V(x)]) => StoreBCD { register: *x },
+        ("LD", [Indirect, V(x)]) => StoreRegistersToMemory { to_register: *x },
+        ("LD", [R, V(x)]) => StoreRegistersToFlags { to_register: *x },
+        ("ADD", [V(x), V(y)]) => AddRegisterToRegister { register_x: *x, register_y: *y },
+        ("ADD", [V(x), Value(b)]) => AddBytesToRegister { register: *x, bytes: byte(b)? },
+        ("ADD", [I, V(x)]) => AddRegisterToI { register: *x },
+        ("OR", [V(x), V(y)]) => BitwiseOr { register_x: *x, register_y: *y },
+        ("AND", [V(x), V(y)]) => BitwiseAnd { register_x: *x, register_y: *y },
+        ("XOR", [V(x), V(y)]) => BitwiseXor { register_x: *x, register_y: *y },
+        ("SUB", [V(x), V(y)]) => SubtractRegisterToRegister { register_x: *x, register_y: *y },
+        ("SUBN", [V(x), V(y)]) => SubtractInversed { register_x: *x, register_y: *y },
+        // Without Vy, shift Vx in place whatever the quirks.
+        ("SHR", [V(x)]) => LeastSignificantBit { register_x: *x, register_y: *x },
+        ("SHR", [V(x), V(y)]) => LeastSignificantBit { register_x: *x, register_y: *y },
+        ("SHL", [V(x)]) => MostSignificantBit { register_x: *x, register_y: *x },
+        ("SHL", [V(x), V(y)]) => MostSignificantBit { register_x: *x, register_y: *y },
+        ("RND", [V(x), Value(b)]) => Random { register: *x, value: byte(b)? },
+        ("DRW", [V(x), V(y), Value(n)]) => match nibble(n)? {
+            0 => DisplayLarge { register_x: *x, register_y: *y },
+            n => Display { register_x: *x, register_y: *y, nibble: n },
+        },
+        ("SKP", [V(x)]) => SkipIfKeyIsPressed { register: *x },
+        ("SKNP", [V(x)]) => SkipIfKeyIsNotPressed { register: *x },
+        ("PLANE", [Value(n)]) => SelectPlanes { planes: nibble(n)? },
+        ("AUDIO", []) => LoadAudioPattern,
+        ("PITCH", [V(x)]) => SetPitch { register: *x },
+        _ if MNEMONICS.contains(&mnemonic) => return Err(format!("invalid operands for `{mnemonic}`")),
+        _ => return Err(format!("unknown instruction `{mnemonic}`")),
+    };
+    Ok(instruction)
+}
+
+const MNEMONICS: [&str; 29] = [
+    "CLS", "RET", "SCD", "SCU", "SCR", "SCL", "EXIT", "LOW", "HIGH", "JP", "CALL", "SE", "SNE", "LD", "ADD", "OR",
+    "AND", "XOR", "SUB", "SUBN", "SHR", "SHL", "RND", "DRW", "SKP", "SKNP", "PLANE", "AUDIO", "PITCH",
+];
+
+fn register(text: &str) -> Option<Data> {
+    let digit = text.strip_prefix(['V', 'v'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    Data::from_str_radix(digit, 16).ok()
+}
+
+/// Parses comma separated operands, `value` parsing those that are neither a
+/// register nor a keyword.
+pub(crate) fn operands<T>(text: &str, value: impl Fn(&str) -> Result<T, String>) -> Result<Vec<Operand<T>>, String> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    text.split(',').map(|o| operand(o.trim(), &value)).collect()
+}
+
+fn operand<T>(text: &str, value: impl Fn(&str) -> Result<T, String>) -> Result<Operand<T>, String> {
+    let operand = match text.to_ascii_uppercase().as_str() {
+        "" => return Err("missing operand".to_string()),
+        "I" => Operand::I,
+        "[I]" => Operand::Indirect,
+        "DT" => Operand::Dt,
+        "ST" => Operand::St,
+        "K" => Operand::K,
+        "F" => Operand::F,
+        "HF" => Operand::Hf,
+        "B" => Operand::B,
+        "R" => Operand::R,
+        "LONG" => Operand::Long(None),
+        upper => {
+            if let Some(x) = register(text) {
+                Operand::V(x)
+            } else if let Some((x, y)) = text.split_once('-').and_then(|(x, y)| Some((register(x.trim())?, register(y.trim())?))) {
+                Operand::Range(x, y)
+            } else if upper.starts_with("LONG ") {
+                Operand::Long(Some(value(text[5..].trim())?))
+            } else {
+                Operand::Value(value(text)?)
+            }
+        },
+    };
+    Ok(operand)
+}
+
+/// Parses a decimal, 0x hexadecimal or 0b binary number, `None` if `word` does
+/// not start like one.
+pub(crate) fn number(word: &str) -> Option<Result<i64, String>> {
+    let lower = word.to_ascii_lowercase();
+    let number = if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2)
+    } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+        word.parse()
+    } else {
+        return None;
+    };
+    Some(number.map_err(|e| match e.kind() {
+        IntErrorKind::PosOverflow => format!("`{word}` is too large"),
+        _ => format!("invalid number `{word}`"),
+    }))
+}
+
+/// Checks that `value` fits in `bits` bits. Bytes can also be written as
+/// negative numbers.
+pub(crate) fn fit(value: i64, bits: u32) -> Result<u16, String> {
+    let max = (1i64 << bits) - 1;
+    let min = if bits == 8 { -128 } else { 0 };
+    if value < min || value > max {
+        return Err(format!("{value} does not fit in {bits} bits"));
+    }
+    Ok((value & max) as u16)
 }
 
 pub fn decode(instr : u16) -> Instruction {
@@ -124,35 +494,48 @@ pub fn decode(instr : u16) -> Instruction {
     //   /\         3 ]    TAIL
     //  /  \     
 
-    let (head, neck, body, tail) = (
+    let (_head, neck, body, tail) = (
         ((instr & 0xF000) >> 12) as u8,
         ((instr & 0x0F00) >> 8) as u8,
         ((instr & 0x00F0) >> 4) as u8,
         (instr & 0x000F) as u8,
     );
 
-    println!("({:x?}) => {:x?} {:x?} {:x?} {:x?}", instr, head, neck, body, tail);  
-
     let bodytail = (instr & 0xFF) as u8; 
 
-    match (upper, lower) {
+    let instruction = match (upper, lower) {
         ( 0x0   , 0xE0  ) => Instruction::Cls,
 
         ( 0x0   , 0xEE  ) => Instruction::Ret,
 
+        ( 0x0   , 0xC0..=0xCF ) => Instruction::ScrollDown { rows: tail },
+
+        ( 0x0   , 0xD0..=0xDF ) => Instruction::ScrollUp { rows: tail },
+
+        ( 0x0   , 0xFB  ) => Instruction::ScrollRight,
+
+        ( 0x0   , 0xFC  ) => Instruction::ScrollLeft,
+
+        ( 0x0   , 0xFD  ) => Instruction::Exit,
+
+        ( 0x0   , 0xFE  ) => Instruction::LowResolution,
+
+        ( 0x0   , 0xFF  ) => Instruction::HighResolution,
+
         ( 0x1000, _     ) => Instruction::Jump { location: lower },
 
         ( 0x2000, _     ) => Instruction::Call { location: lower },
 
         ( 0x3000, _ ) => Instruction::SkipEqualRegisterBytes { register_index: neck, bytes: bodytail },
 
-        ( 0x4000, _ ) => Instruction::SkipNotEqualRegisterBytes { register_index: neck as u8, bytes: bodytail },
+        ( 0x4000, _ ) => Instruction::SkipNotEqualRegisterBytes { register_index: neck, bytes: bodytail },
 
         ( 0x5000, _) => {
-            if tail != 0 {
-                Instruction::Invalid
-            } else {
-                Instruction::SkipEqualRegisterRegister { register_x: neck, register_y: body }
+            match tail {
+                0 => Instruction::SkipEqualRegisterRegister { register_x: neck, register_y: body },
+                2 => Instruction::StoreRegisterRange { register_x: neck, register_y: body },
+                3 => Instruction::LoadRegisterRange { register_x: neck, register_y: body },
+                _ => Instruction::Invalid { opcode: instr }
             }
         },
 
@@ -168,16 +551,16 @@ pub fn decode(instr : u16) -> Instruction {
                 3 => Instruction::BitwiseXor { register_x: neck, register_y: body },
                 4 => Instruction::AddRegisterToRegister { register_x: neck, register_y: body },
                 5 => Instruction::SubtractRegisterToRegister { register_x: neck, register_y: body },
-                6 => Instruction::LeastSignificantBit { register: neck },
+                6 => Instruction::LeastSignificantBit { register_x: neck, register_y: body },
                 7 => Instruction::SubtractInversed { register_x: neck, register_y: body },
-                0xE => Instruction::MostSignificantBit { register: neck },
-                _ => Instruction::Invalid
+                0xE => Instruction::MostSignificantBit { register_x: neck, register_y: body },
+                _ => Instruction::Invalid { opcode: instr }
             }
         },
 
         (0x9000, _ ) => {
             if tail != 0 {
-                Instruction::Invalid
+                Instruction::Invalid { opcode: instr }
             } else {
                 Instruction::SkipNotEqualRegisterRegister { register_x: neck, register_y: body }
             }
@@ -186,28 +569,79 @@ pub fn decode(instr : u16) -> Instruction {
         (0xA000, lower ) =>  Instruction::SetI { value: lower },
         (0xB000, lower) => Instruction::JumpToLocationPlusZeroRegister { address: lower },
         (0xC000, _ ) => Instruction::Random { register: neck, value: bodytail },
-        (0xD000, _ ) => Instruction::Display { register_x: neck, register_y: body, nibble: tail },
+        (0xD000, _ ) => {
+            if tail == 0 {
+                Instruction::DisplayLarge { register_x: neck, register_y: body }
+            } else {
+                Instruction::Display { register_x: neck, register_y: body, nibble: tail }
+            }
+        },
         (0xE000, _ ) => {
             match bodytail {
                 0x9E => Instruction::SkipIfKeyIsPressed { register: neck },
                 0xA1 => Instruction::SkipIfKeyIsNotPressed { register: neck },
-                _ => Instruction::Invalid
+                _ => Instruction::Invalid { opcode: instr }
             }
         },
+        (0xF000, 0x000) => Instruction::SetILong,
+        (0xF000, 0x002) => Instruction::LoadAudioPattern,
         (0xF000, _ ) => {
             match bodytail {
+                0x01 => Instruction::SelectPlanes { planes: neck },
                 0x07 => Instruction::SetRegisterToDelayTimer { register: neck },
                 0x0A => Instruction::WaitForKey { register: neck },
                 0x15 => Instruction::SetDelayTimer { register: neck },
                 0x18 => Instruction::SetSoundTimer { register: neck },
                 0x1E => Instruction::AddRegisterToI { register: neck },
                 0x29 => Instruction::SetIToLocationOfSprite { register: neck },
+                0x30 => Instruction::SetIToLocationOfLargeSprite { register: neck },
                 0x33 => Instruction::StoreBCD { register: neck },
+                0x3A => Instruction::SetPitch { register: neck },
                 0x55 => Instruction::StoreRegistersToMemory { to_register: neck },
                 0x65 => Instruction::LoadRegistersFromMemory { to_register: neck },
-                _ => Instruction::Invalid
+                0x75 => Instruction::StoreRegistersToFlags { to_register: neck },
+                0x85 => Instruction::LoadRegistersFromFlags { to_register: neck },
+                _ => Instruction::Invalid { opcode: instr }
             }
         }
-        _ => Instruction::Invalid
+        _ => Instruction::Invalid { opcode: instr }
+    };
+    log::trace!(target: "decoder", "{instr:04x}: {instruction:?}");
+    instruction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_is_the_inverse_of_decode() {
+        for word in 0..=u16::MAX {
+            let instruction = decode(word);
+            if !matches!(instruction, Instruction::Invalid { .. }) {
+                assert_eq!(instruction.encode(), word, "{instruction:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn mnemonics_parse_back_to_the_same_instruction() {
+        for word in 0..=u16::MAX {
+            let instruction = decode(word);
+            if !matches!(instruction, Instruction::Invalid { .. }) {
+                assert_eq!(instruction.to_string().parse(), Ok(instruction), "{instruction}");
+            }
+        }
+    }
+
+    #[test]
+    fn mnemonics_follow_cowgod() {
+        assert_eq!(decode(0x6310).to_string(), "LD V3, 0x10");
+        assert_eq!(decode(0xD015).to_string(), "DRW V0, V1, 5");
+        assert_eq!(decode(0x5142).to_string(), "LD [I], V1 - V4");
+        assert_eq!("ld v3, 16".parse(), Ok(decode(0x6310)));
+        assert!("LD V3".parse::<Instruction>().is_err());
+        assert_eq!("LD V3, label".parse::<Instruction>(), Err("invalid value `label`".to_string()));
+        assert_eq!("LD I, LONG 0x1234".parse(), Ok(Instruction::SetILong));
     }
 }
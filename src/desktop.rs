@@ -0,0 +1,104 @@
+use std::{cell::RefCell, rc::Rc};
+
+use c8::io::{Display, Input, Keypad, Palette, Screen, HEIGHT, WIDTH};
+use minifb::{Key, KeyRepeat, Scale, Window, WindowOptions};
+
+use crate::keymap::{Keymap, SLOT_KEYS};
+
+/// Frontend actions bound to keys of the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    /// Shift+F1 to Shift+F9.
+    SaveState(u8),
+    /// F1 to F9.
+    LoadState(u8),
+    /// F10.
+    Pause,
+}
+
+/// The minifb frontend: one window, shared by the display and the input.
+pub struct Desktop {
+    window: Rc<RefCell<Window>>,
+    palette: Palette,
+    keymap: Keymap,
+}
+
+impl Desktop {
+    pub fn new(scale: Scale, palette: Palette, keymap: Keymap) -> Result<Desktop, minifb::Error> {
+        // Pacing is left to the caller's scheduler, which presents once per frame.
+        let window = Window::new(
+            "C8 - ESC to exit",
+            WIDTH,
+            HEIGHT,
+            WindowOptions { scale, ..Default::default() },
+        )?;
+        Ok(Desktop { window: Rc::new(RefCell::new(window)), palette, keymap })
+    }
+
+    /// False once the window has been closed or Escape has been pressed.
+    pub fn is_open(&self) -> bool {
+        let window = self.window.borrow();
+        window.is_open() && !window.is_key_down(Key::Escape)
+    }
+
+    /// True while Backspace, the rewind key, is held down.
+    pub fn is_rewinding(&self) -> bool {
+        self.window.borrow().is_key_down(Key::Backspace)
+    }
+
+    /// True while Tab, the fast-forward key, is held down.
+    pub fn is_fast_forwarding(&self) -> bool {
+        self.window.borrow().is_key_down(Key::Tab)
+    }
+
+    /// The hotkeys pressed since the window was last updated.
+    pub fn hotkeys(&self) -> Vec<Hotkey> {
+        let window = self.window.borrow();
+        let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        window
+            .get_keys_pressed(KeyRepeat::No)
+            .into_iter()
+            .filter_map(|key| match SLOT_KEYS.iter().position(|k| *k == key) {
+                Some(slot) if shift => Some(Hotkey::SaveState(slot as u8 + 1)),
+                Some(slot) => Some(Hotkey::LoadState(slot as u8 + 1)),
+                None => (key == Key::F10).then_some(Hotkey::Pause),
+            })
+            .collect()
+    }
+
+    pub fn display(&self) -> Box<dyn Display> {
+        Box::new(DesktopDisplay { window: Rc::clone(&self.window), palette: self.palette })
+    }
+
+    pub fn input(&self) -> Box<dyn Input> {
+        Box::new(DesktopInput { window: Rc::clone(&self.window), keymap: self.keymap.clone() })
+    }
+}
+
+struct DesktopDisplay {
+    window: Rc<RefCell<Window>>,
+    palette: Palette,
+}
+
+impl Display for DesktopDisplay {
+    fn present(&mut self, screen: &Screen) {
+        self.window
+            .borrow_mut()
+            .update_with_buffer(&screen.to_buffer(self.palette), screen.width(), screen.height())
+            .unwrap();
+    }
+}
+
+struct DesktopInput {
+    window: Rc<RefCell<Window>>,
+    keymap: Keymap,
+}
+
+impl Input for DesktopInput {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        let window = self.window.borrow();
+        for key in 0..16 {
+            keypad.set(key, self.keymap.keys(key).iter().any(|k| window.is_key_down(*k)));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use crate::decoder::{self, Instruction};
+use crate::types::{AddressLong, Data};
+
+/// Where programs are loaded, and where tracing starts.
+pub const START: usize = 0x200;
+
+/// The assembly language mnemonics are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+    /// Cowgod's Chip-8 technical reference: `LD V3, 0x10`.
+    #[default]
+    Cowgod,
+    /// Octo: `v3 := 0x10`.
+    Octo,
+}
+
+impl FromStr for Syntax {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cowgod" => Ok(Syntax::Cowgod),
+            "octo" => Ok(Syntax::Octo),
+            _ => Err(()),
+        }
+    }
+}
+
+/// An instruction read from memory, with the word following F000.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decoded {
+    pub opcode: u16,
+    pub long: Option<u16>,
+    pub instruction: Instruction,
+}
+
+impl Decoded {
+    /// Decodes the instruction at `index`, or `None` if it does not fit in `bytes`.
+    pub fn at(bytes: &[Data], index: usize) -> Option<Decoded> {
+        let word = |at: usize| Some(u16::from_be_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]));
+        let opcode = word(index)?;
+        let instruction = decoder::decode(opcode);
+        let long = match instruction {
+            Instruction::SetILong => Some(word(index + 2)?),
+            _ => None,
+        };
+        Some(Decoded { opcode, long, instruction })
+    }
+
+    /// In bytes.
+    pub fn size(&self) -> usize {
+        if self.long.is_some() { 4 } else { 2 }
+    }
+
+    /// The opcode, and the word following F000, in hexadecimal.
+    pub fn raw(&self) -> String {
+        match self.long {
+            Some(long) => format!("{:04X} {long:04X}", self.opcode),
+            None => format!("{:04X}", self.opcode),
+        }
+    }
+
+    pub fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Cowgod => self.cowgod(),
+            Syntax::Octo => self.octo(),
+        }
+    }
+
+    fn cowgod(&self) -> String {
+        match self.long {
+            Some(long) => format!("{} {long:#06x}", self.instruction),
+            None => self.instruction.to_string(),
+        }
+    }
+
+    fn octo(&self) -> String {
+        use Instruction::*;
+        match self.instruction {
+            Cls => "clear".to_string(),
+            Ret => "return".to_string(),
+            ScrollDown { rows } => format!("scroll-down {rows}"),
+            ScrollUp { rows } => format!("scroll-up {rows}"),
+            ScrollRight => "scroll-right".to_string(),
+            ScrollLeft => "scroll-left".to_string(),
+            Exit => "exit".to_string(),
+            LowResolution => "lores".to_string(),
+            HighResolution => "hires".to_string(),
+            Jump { location } => format!("jump {location:#05x}"),
+            Call { location } => format!(":call {location:#05x}"),
+            // Octo conditions tell when the next instruction runs, the opposite of the skip.
+            SkipEqualRegisterBytes { register_index, bytes } => format!("if v{register_index:x} != {bytes:#04x} then"),
+            SkipNotEqualRegisterBytes { register_index, bytes } => format!("if v{register_index:x} == {bytes:#04x} then"),
+            SkipEqualRegisterRegister { register_x, register_y } => format!("if v{register_x:x} != v{register_y:x} then"),
+            StoreRegisterRange { register_x, register_y } => format!("save v{register_x:x} - v{register_y:x}"),
+            LoadRegisterRange { register_x, register_y } => format!("load v{register_x:x} - v{register_y:x}"),
+            SetRegisterToBytes { register, bytes } => format!("v{register:x} := {bytes:#04x}"),
+            AddBytesToRegister { register, bytes } => format!("v{register:x} += {bytes:#04x}"),
+            SetRegisterToRegister { register_x, register_y } => format!("v{register_x:x} := v{register_y:x}"),
+            BitwiseOr { register_x, register_y } => format!("v{register_x:x} |= v{register_y:x}"),
+            BitwiseAnd { register_x, register_y } => format!("v{register_x:x} &= v{register_y:x}"),
+            BitwiseXor { register_x, register_y } => format!("v{register_x:x} ^= v{register_y:x}"),
+            AddRegisterToRegister { register_x, register_y } => format!("v{register_x:x} += v{register_y:x}"),
+            SubtractRegisterToRegister { register_x, register_y } => format!("v{register_x:x} -= v{register_y:x}"),
+            LeastSignificantBit { register_x, register_y } => format!("v{register_x:x} >>= v{register_y:x}"),
+            SubtractInversed { register_x, register_y } => format!("v{register_x:x} =- v{register_y:x}"),
+            MostSignificantBit { register_x, register_y } => format!("v{register_x:x} <<= v{register_y:x}"),
+            SkipNotEqualRegisterRegister { register_x, register_y } => format!("if v{register_x:x} == v{register_y:x} then"),
+            SetI { value } => format!("i := {value:#05x}"),
+            JumpToLocationPlusZeroRegister { address } => format!("jump0 {address:#05x}"),
+            Random { register, value } => format!("v{register:x} := random {value:#04x}"),
+            Display { register_x, register_y, nibble } => format!("sprite v{register_x:x} v{register_y:x} {nibble}"),
+            DisplayLarge { register_x, register_y } => format!("sprite v{register_x:x} v{register_y:x} 0"),
+            SkipIfKeyIsPressed { register } => format!("if v{register:x} -key then"),
+            SkipIfKeyIsNotPressed { register } => format!("if v{register:x} key then"),
+            SetILong => format!("i := long {:#06x}", self.long.unwrap_or_default()),
+            SelectPlanes { planes } => format!("plane {planes}"),
+            LoadAudioPattern => "audio".to_string(),
+            SetRegisterToDelayTimer { register } => format!("v{register:x} := delay"),
+            WaitForKey { register } => format!("v{register:x} := key"),
+            SetDelayTimer { register } => format!("delay := v{register:x}"),
+            SetSoundTimer { register } => format!("buzzer := v{register:x}"),
+            AddRegisterToI { register } => format!("i += v{register:x}"),
+            SetIToLocationOfSprite { register } => format!("i := hex v{register:x}"),
+            SetIToLocationOfLargeSprite { register } => format!("i := bighex v{register:x}"),
+            StoreBCD { register } => format!("bcd v{register:x}"),
+            SetPitch { register } => format!("pitch := v{register:x}"),
+            StoreRegistersToMemory { to_register } => format!("save v{to_register:x}"),
+            LoadRegistersFromMemory { to_register } => format!("load v{to_register:x}"),
+            StoreRegistersToFlags { to_register } => format!("saveflags v{to_register:x}"),
+            LoadRegistersFromFlags { to_register } => format!("loadflags v{to_register:x}"),
+            Invalid { opcode } => format!("{:#04x} {:#04x}", opcode >> 8, opcode & 0xFF),
+        }
+    }
+}
+
+/// Follows every path the program can take from 0x200 and returns the offsets,
+/// in `rom`, of the instructions reached. The rest is taken to be data.
+/// Jumps through Bnnn cannot be followed.
+pub fn trace(rom: &[Data]) -> BTreeSet<usize> {
+    let mut code = BTreeSet::new();
+    let mut pending = vec![START];
+    while let Some(address) = pending.pop() {
+        let Some(index) = address.checked_sub(START) else { continue };
+        if code.contains(&index) {
+            continue;
+        }
+        let Some(decoded) = Decoded::at(rom, index) else { continue };
+        if matches!(decoded.instruction, Instruction::Invalid { .. }) {
+            continue;
+        }
+        code.insert(index);
+
+        let next = address + decoded.size();
+        match decoded.instruction {
+            Instruction::Jump { location } => pending.push(location as usize),
+            Instruction::Call { location } => {
+                pending.push(location as usize);
+                pending.push(next);
+            },
+            Instruction::Ret | Instruction::Exit | Instruction::JumpToLocationPlusZeroRegister { .. } => {},
+            Instruction::SkipEqualRegisterBytes { .. }
+            | Instruction::SkipNotEqualRegisterBytes { .. }
+            | Instruction::SkipEqualRegisterRegister { .. }
+            | Instruction::SkipNotEqualRegisterRegister { .. }
+            | Instruction::SkipIfKeyIsPressed { .. }
+            | Instruction::SkipIfKeyIsNotPressed { .. } => {
+                pending.push(next);
+                // Skipping F000 skips its address as well.
+                let skipped = Decoded::at(rom, next - START).map_or(2, |d| d.size());
+                pending.push(next + skipped);
+            },
+            _ => pending.push(next),
+        }
+    }
+    code
+}
+
+/// One line per instruction or data byte: address, raw bytes and mnemonic.
+/// With `linear`, every word is decoded as an instruction instead of tracing.
+pub fn disassemble(rom: &[Data], syntax: Syntax, linear: bool) -> String {
+    let code = if linear { (0..rom.len()).step_by(2).collect() } else { trace(rom) };
+    let mut out = String::new();
+    let mut index = 0;
+    while index < rom.len() {
+        let address = (START + index) as AddressLong;
+        let decoded = code.contains(&index).then(|| Decoded::at(rom, index)).flatten();
+        match decoded {
+            Some(decoded) => {
+                out += &format!("{address:#06x}  {:<9}  {}\n", decoded.raw(), decoded.mnemonic(syntax));
+                index += decoded.size();
+            },
+            None => {
+                let byte = rom[index];
+                let bits: String = (0..8).rev().map(|bit| if byte >> bit & 1 == 1 { '#' } else { '.' }).collect();
+                match syntax {
+                    Syntax::Cowgod => out += &format!("{address:#06x}  {byte:02X}         DB {byte:#04x}  ; {bits}\n"),
+                    Syntax::Octo => out += &format!("{address:#06x}  {byte:02X}         {byte:#04x}  # {bits}\n"),
+                }
+                index += 1;
+            },
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mnemonic(bytes: &[Data], syntax: Syntax) -> String {
+        Decoded::at(bytes, 0).unwrap().mnemonic(syntax)
+    }
+
+    #[test]
+    fn mnemonics_in_both_syntaxes() {
+        assert_eq!(mnemonic(&[0x63, 0x10], Syntax::Cowgod), "LD V3, 0x10");
+        assert_eq!(mnemonic(&[0xD0, 0x15], Syntax::Cowgod), "DRW V0, V1, 5");
+        assert_eq!(mnemonic(&[0xF0, 0x00, 0x12, 0x34], Syntax::Cowgod), "LD I, LONG 0x1234");
+        assert_eq!(mnemonic(&[0x63, 0x10], Syntax::Octo), "v3 := 0x10");
+        assert_eq!(mnemonic(&[0xD0, 0x15], Syntax::Octo), "sprite v0 v1 5");
+        assert_eq!(mnemonic(&[0x3A, 0x01], Syntax::Octo), "if va != 0x01 then");
+    }
+
+    #[test]
+    fn tracing_separates_code_from_sprites() {
+        // LD I, 0x208; DRW V0, V0, 2; SE V0, 0; JP 0x206; then a 2 byte sprite
+        let rom = [0xA2, 0x08, 0xD0, 0x02, 0x30, 0x00, 0x12, 0x06, 0xFF, 0x81];
+        assert_eq!(trace(&rom).into_iter().collect::<Vec<_>>(), [0, 2, 4, 6]);
+
+        let listing = disassemble(&rom, Syntax::Cowgod, false);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines[0], "0x0200  A208       LD I, 0x208");
+        assert_eq!(lines[4], "0x0208  FF         DB 0xff  ; ########");
+        assert_eq!(lines.len(), 6);
+    }
+}
@@ -1,5 +1,41 @@
-#[derive(Debug)]
+use std::fmt;
+
+use crate::types::{AddressLong, Data};
+
+/// Everything that can stop the interpreter.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum C8Err {
+    /// A CALL with every level of the stack already in use.
     StackOverflow,
-    MemoryUnaccessible,
-}
\ No newline at end of file
+    /// A RET with nothing on the stack.
+    StackUnderflow,
+    /// A read past the end of memory.
+    ReadOutOfRange { address: usize },
+    /// A write past the end of memory.
+    WriteOutOfRange { address: usize },
+    /// A word that is not an instruction, found at `address`.
+    InvalidOpcode { opcode: u16, address: AddressLong },
+    /// SKP/SKNP with a register holding something other than a key from 0 to F.
+    BadKey { key: Data },
+    /// A program that does not fit in memory.
+    RomTooLarge { size: usize },
+    /// `Chip::run_until` ran out of cycles before its condition was met.
+    CycleLimit { cycles: u64 },
+}
+
+impl fmt::Display for C8Err {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            C8Err::StackOverflow => write!(f, "stack overflow"),
+            C8Err::StackUnderflow => write!(f, "return with an empty stack"),
+            C8Err::ReadOutOfRange { address } => write!(f, "read out of memory at {address:#06x}"),
+            C8Err::WriteOutOfRange { address } => write!(f, "write out of memory at {address:#06x}"),
+            C8Err::InvalidOpcode { opcode, address } => write!(f, "invalid opcode {opcode:04x} at {address:#05x}"),
+            C8Err::BadKey { key } => write!(f, "there is no key {key:#04x}"),
+            C8Err::RomTooLarge { size } => write!(f, "a {size} bytes long program does not fit in memory"),
+            C8Err::CycleLimit { cycles } => write!(f, "gave up after {cycles} cycles"),
+        }
+    }
+}
+
+impl std::error::Error for C8Err {}
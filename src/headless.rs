@@ -0,0 +1,124 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::c8::Chip;
+use crate::io::{Audio, Display, Input, Keypad, Palette, Screen};
+use crate::types::Data;
+
+#[derive(Debug, Default)]
+struct State {
+    frame: Screen,
+    frames: usize,
+    keys: Keypad,
+    playing: bool,
+    tone: Vec<ToneEvent>,
+    pattern: Option<([Data; 16], Data)>,
+}
+
+/// When the tone started or stopped, counted in presented frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneEvent {
+    pub frame: usize,
+    pub playing: bool,
+}
+
+/// A frontend that keeps everything in memory, for servers and tests.
+///
+/// Clones share the same state: give one to the `Chip` and keep another one
+/// to inspect the last presented frame or to press keys.
+#[derive(Debug, Clone, Default)]
+pub struct Headless {
+    state: Rc<RefCell<State>>,
+}
+
+impl Headless {
+    pub fn new() -> Headless {
+        Headless::default()
+    }
+
+    /// Builds a `Chip` whose display, input and audio are all this frontend.
+    pub fn chip(&self) -> Chip {
+        Chip::new(Box::new(self.clone()), Box::new(self.clone()), Box::new(self.clone()))
+    }
+
+    /// A copy of the last presented frame.
+    pub fn frame(&self) -> Screen {
+        self.state.borrow().frame.clone()
+    }
+
+    pub fn to_buffer(&self, palette: Palette) -> Vec<u32> {
+        self.state.borrow().frame.to_buffer(palette)
+    }
+
+    /// How many times the `Chip` has presented its screen.
+    pub fn frames(&self) -> usize {
+        self.state.borrow().frames
+    }
+
+    pub fn press(&self, key: Data) {
+        self.set_key(key, true);
+    }
+
+    pub fn release(&self, key: Data) {
+        self.set_key(key, false);
+    }
+
+    pub fn set_key(&self, key: Data, down: bool) {
+        self.state.borrow_mut().keys.set(key, down);
+    }
+
+    pub fn set_keys(&self, keys: [bool; 16]) {
+        let mut state = self.state.borrow_mut();
+        for (key, down) in keys.into_iter().enumerate() {
+            state.keys.set(key as Data, down);
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state.borrow().playing
+    }
+
+    /// Every time the tone has been started or stopped, oldest first.
+    pub fn tone(&self) -> Vec<ToneEvent> {
+        self.state.borrow().tone.clone()
+    }
+
+    /// The last XO-CHIP audio pattern and pitch, if the program set any.
+    pub fn pattern(&self) -> Option<([Data; 16], Data)> {
+        self.state.borrow().pattern
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        let mut state = self.state.borrow_mut();
+        let frame = state.frames;
+        state.playing = playing;
+        state.tone.push(ToneEvent { frame, playing });
+    }
+}
+
+impl Display for Headless {
+    fn present(&mut self, screen: &Screen) {
+        let mut state = self.state.borrow_mut();
+        state.frame.clone_from(screen);
+        state.frames += 1;
+    }
+}
+
+impl Input for Headless {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        *keypad = self.state.borrow().keys;
+    }
+}
+
+impl Audio for Headless {
+    fn start(&mut self) {
+        self.set_playing(true);
+    }
+
+    fn stop(&mut self) {
+        self.set_playing(false);
+    }
+
+    fn set_pattern(&mut self, pattern: &[Data; 16], pitch: Data) {
+        self.state.borrow_mut().pattern = Some((*pattern, pitch));
+    }
+}
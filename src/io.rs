@@ -1,151 +1,587 @@
-use std::{error::Error, thread, time::Duration, fmt::format};
-
-use minifb::{Window, Scale, WindowOptions, Key};
+use std::str::FromStr;
 
 use crate::types::Data;
 
 pub const HEIGHT : usize = 32;
 pub const WIDTH : usize = 64;
 
+/// The SUPER-CHIP high resolution mode.
+pub const HIRES_HEIGHT : usize = 64;
+pub const HIRES_WIDTH : usize = 128;
+
+/// Programs are loaded at 0x200 and may fill the rest of the 64 KiB XO-CHIP address space.
+pub const MAX_ROM_SIZE : usize = 0x10000 - 0x200;
+
+/// Colours used when presenting the screen.
+///
+/// `on` and `off` are all CHIP-8 and SUPER-CHIP programs use, `second` and
+/// `both` are for the XO-CHIP pixels lit in the second plane or in both planes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub on: u32,
+    pub off: u32,
+    pub second: u32,
+    pub both: u32,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette { on: 0xFF0000, off: 0x000000, second: 0x00A0FF, both: 0xFFFFFF }
+    }
+}
+
+impl Palette {
+    /// The colour of a pixel, given the planes it is lit in.
+    pub fn color(&self, planes: u8) -> u32 {
+        match planes & 0b11 {
+            0 => self.off,
+            1 => self.on,
+            2 => self.second,
+            _ => self.both,
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = ();
+
+    /// Parses `on,off` or `on,off,second,both`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |c: &str| u32::from_str_radix(c.trim().trim_start_matches('#'), 16)
+            .ok()
+            .filter(|v| *v <= 0xFFFFFF)
+            .ok_or(());
+        let colors = s.split(',').map(parse).collect::<Result<Vec<u32>, ()>>()?;
+        match colors[..] {
+            [on, off] => Ok(Palette { on, off, ..Palette::default() }),
+            [on, off, second, both] => Ok(Palette { on, off, second, both }),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Shows the content of the screen to the user.
+pub trait Display {
+    fn present(&mut self, screen: &Screen);
+}
+
+/// Which of the 16 keys of the hexadecimal keypad are held down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Keypad {
+    keys: u16, // one bit per key
+}
+
+impl Keypad {
+    pub fn new() -> Keypad {
+        Keypad::default()
+    }
+
+    /// False for anything above 0xF.
+    pub fn is_down(&self, key: Data) -> bool {
+        key < 16 && self.keys >> key & 1 == 1
+    }
+
+    /// Keys above 0xF are ignored.
+    pub fn set(&mut self, key: Data, down: bool) {
+        if key < 16 {
+            if down {
+                self.keys |= 1 << key;
+            } else {
+                self.keys &= !(1 << key);
+            }
+        }
+    }
+
+    pub fn press(&mut self, key: Data) {
+        self.set(key, true);
+    }
+
+    pub fn release(&mut self, key: Data) {
+        self.set(key, false);
+    }
+
+    /// One bit per key, key 0 being the lowest bit.
+    pub fn bits(&self) -> u16 {
+        self.keys
+    }
+
+    pub fn from_bits(keys: u16) -> Keypad {
+        Keypad { keys }
+    }
+
+    /// The lowest key held down, if any.
+    pub fn first_down(&self) -> Option<Data> {
+        (0..16).find(|key| self.is_down(*key))
+    }
+}
+
+/// Reads the keys from the user, once per frame.
+pub trait Input {
+    fn poll(&mut self, keypad: &mut Keypad);
+}
+
+/// Plays the tone requested by the sound timer.
+pub trait Audio {
+    fn start(&mut self);
+    fn stop(&mut self);
+
+    /// XO-CHIP programs replace the tone with a 1-bit, 128 samples long
+    /// pattern played at `pattern_rate(pitch)` samples per second.
+    fn set_pattern(&mut self, _pattern: &[Data; 16], _pitch: Data) {}
+
+    /// Called once per 60 Hz frame of emulated time, for backends that keep time.
+    fn frame(&mut self) {}
+}
+
+/// The XO-CHIP playback rate, in samples per second, for a pitch register value.
+pub fn pattern_rate(pitch: Data) -> f64 {
+    4000.0 * 2f64.powf((pitch as f64 - 64.0) / 48.0)
+}
+
+/// An `Audio` implementation that stays silent, see the `audio` module for the others.
+pub struct NullAudio;
+
+impl Audio for NullAudio {
+    fn start(&mut self) {}
+    fn stop(&mut self) {}
+}
+
+/// A `Display` that shows nothing, the screen stays readable through `Chip::screen`.
+pub struct NullDisplay;
+
+impl Display for NullDisplay {
+    fn present(&mut self, _screen: &Screen) {}
+}
+
+/// An `Input` whose keys are never pressed, they can still be set through `Chip::keypad_mut`.
+pub struct NullInput;
+
+impl Input for NullInput {
+    fn poll(&mut self, _keypad: &mut Keypad) {}
+}
+
+/// What happens to the part of a sprite that crosses the edge of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpriteEdge {
+    /// Pixels past the edge are dropped, as on the COSMAC VIP.
+    #[default]
+    Clip,
+    /// Pixels past the edge reappear on the opposite side.
+    Wrap,
+}
+
+/// The framebuffer, kept by the interpreter independently of any frontend.
+///
+/// It starts in the 64x32 resolution of CHIP-8 and can be switched to the
+/// 128x64 one of SUPER-CHIP. Every pixel is made of the two XO-CHIP bitplanes,
+/// drawing and scrolling only affect the selected ones.
+#[derive(Debug, Clone)]
 pub struct Screen {
-    /// False is `off`, True is `on`
-    screen: [[bool; WIDTH]; HEIGHT],
-    pub window: Window,
+    /// One bit per plane, row after row
+    screen: Vec<u8>,
+    hires: bool,
+    planes: u8,
+    edge: SpriteEdge,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen::new()
+    }
 }
 
 impl Screen {
     pub fn new() -> Screen {
-        let mut window_options = WindowOptions::default();
-        window_options.scale = Scale::X16;
+        Screen {
+            screen: vec![0; WIDTH * HEIGHT],
+            hires: false,
+            planes: 0b01,
+            edge: SpriteEdge::default(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires { HIRES_WIDTH } else { WIDTH }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires { HIRES_HEIGHT } else { HEIGHT }
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Switches between 64x32 and 128x64, clearing every plane.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.screen = vec![0; self.width() * self.height()];
+    }
+
+    /// The planes affected by drawing, clearing and scrolling, as a bit mask.
+    pub fn planes(&self) -> u8 {
+        self.planes
+    }
+
+    pub fn set_planes(&mut self, planes: u8) {
+        self.planes = planes & 0b11;
+    }
 
-        let mut screen = Screen {
-            screen: [[true; WIDTH]; HEIGHT],
-            window: Window::new(
-                "Test - ESC to exit",
-                WIDTH,
-                HEIGHT,
-                WindowOptions { scale: Scale::X16, ..Default::default() },
-                )
-                .unwrap_or_else(|e| {
-                panic!("{}", e);
-            })
-        }; 
+    pub fn edge(&self) -> SpriteEdge {
+        self.edge
+    }
+
+    pub fn set_edge(&mut self, edge: SpriteEdge) {
+        self.edge = edge;
+    }
+
+    /// The planes each pixel is lit in, row after row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.screen
+    }
 
-        screen.window.limit_update_rate
-            (Some(std::time::Duration::from_micros(16600)));
-        screen
+    /// Replaces every pixel, e.g. when restoring a save state. Returns `false`,
+    /// leaving the screen untouched, unless there is one value from 0 to 3 per pixel.
+    pub fn set_pixels(&mut self, pixels: &[u8]) -> bool {
+        if pixels.len() != self.screen.len() || pixels.iter().any(|p| *p > 0b11) {
+            return false;
+        }
+        self.screen.copy_from_slice(pixels);
+        true
     }
 
-    pub fn get(&self, x: usize, y: usize) -> Option<&bool> {
-        self.screen.get(y)?
-            .get(x)
+    /// Whether the pixel is lit in any plane.
+    pub fn get(&self, x: usize, y: usize) -> Option<bool> {
+        self.pixel(x, y).map(|planes| planes != 0)
     }
 
-    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut bool> {
-        self.screen.get_mut(y)?.get_mut(x)
+    /// The planes the pixel is lit in, as a bit mask.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<u8> {
+        if x >= self.width() {
+            return None;
+        }
+        self.screen.get(y * self.width() + x).copied()
+    }
+
+    fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut u8> {
+        if x >= self.width() {
+            return None;
+        }
+        let width = self.width();
+        self.screen.get_mut(y * width + x)
     }
 
-    pub fn set(&mut self, value: bool, x: usize, y: usize) -> Result<(), ()> {
+    /// Turns the pixel on or off in the selected planes.
+    /// Returns `false` when the coordinates fall outside of the screen.
+    pub fn set(&mut self, value: bool, x: usize, y: usize) -> bool {
+        let planes = self.planes;
         match self.get_mut(x, y) {
             Some(x) => {
-                *x = value;
-                Ok(())
+                if value {
+                    *x |= planes;
+                } else {
+                    *x &= !planes;
+                }
+                true
             },
             None => {
-                Err(())
+                false
             },
         }
-    }   
+    }
 
+    /// Turns off every pixel of the selected planes.
     pub fn clear(&mut self){
-        self.screen = [[false; WIDTH]; HEIGHT];
+        let planes = self.planes;
+        self.screen.iter_mut().for_each(|pixel| *pixel &= !planes);
+    }
+
+    /// XORs a sprite onto the screen, returning `true` if any pixel was turned off.
+    ///
+    /// The starting coordinates always wrap around the screen, while the pixels
+    /// crossing an edge are clipped or wrapped according to `SpriteEdge`.
+    /// With both planes selected the sprite holds the rows of the first plane
+    /// followed by the ones of the second.
+    pub fn draw(&mut self, x: usize, y: usize, sprite: &[Data]) -> bool {
+        self.draw_planes(x, y, sprite, 1)
+    }
+
+    /// Like `draw`, for the 16x16 sprites of SUPER-CHIP: two bytes per row.
+    pub fn draw_large(&mut self, x: usize, y: usize, sprite: &[Data]) -> bool {
+        self.draw_planes(x, y, sprite, 2)
+    }
+
+    /// How many planes are selected, sprites are that many times longer.
+    pub fn plane_count(&self) -> usize {
+        self.planes.count_ones() as usize
     }
 
-    /// This function allows to draw fonts on the screen.
-    pub fn draw(&mut self, x: usize, y: usize, sprite: &[Data]) {
-        // Converts the sprite from a list of numbers to a list of printable booleans.
-        let printable : Vec<[bool; 8]> = sprite.iter().map(|x| -> [bool; 8] {Screen::u8_to_bools(x)}).collect();
-    
+    fn draw_planes(&mut self, x: usize, y: usize, sprite: &[Data], bytes_per_row: usize) -> bool {
+        if self.plane_count() == 0 || sprite.is_empty() {
+            return false;
+        }
+        let per_plane = sprite.len().div_ceil(self.plane_count());
+        let selected = self.planes;
+        let planes = [0b01, 0b10].into_iter().filter(|plane| selected & plane != 0);
+
+        let mut collision = false;
+        for (plane, data) in planes.zip(sprite.chunks(per_plane)) {
+            // Converts the sprite from a list of numbers to a list of printable booleans.
+            let printable : Vec<Vec<bool>> = data.chunks(bytes_per_row)
+                .map(|row| row.iter().flat_map(Screen::u8_to_bools).collect())
+                .collect();
+            collision |= self.draw_printable(x, y, &printable, plane);
+        }
+        collision
+    }
+
+    fn draw_printable(&mut self, x: usize, y: usize, printable: &[Vec<bool>], plane: u8) -> bool {
+        let (width, height) = (self.width(), self.height());
+        let (x, y) = (x % width, y % height);
+        let mut collision = false;
+
         // For each row and column in the printable sprite,
-        // Now print the cell at the coordinates (row + y) and (column + x)
+        // flip the cell at the coordinates (row + y) and (column + x)
         for (row, printable_row) in printable.iter().enumerate() {
             for (column, cell) in printable_row.iter().enumerate() {
-                println!("row + y: {}, column + x: {}", row + y, column + x);
-                self.set( *cell, column + x, row + y);
+                if !*cell {
+                    continue;
+                }
+                let (x, y) = match self.edge {
+                    SpriteEdge::Clip if column + x >= width || row + y >= height => continue,
+                    SpriteEdge::Clip => (column + x, row + y),
+                    SpriteEdge::Wrap => ((column + x) % width, (row + y) % height),
+                };
+                log::trace!(target: "display", "flip ({x}, {y}) in plane {plane}");
+                let pixel = &mut self.screen[y * width + x];
+                collision |= *pixel & plane != 0;
+                *pixel ^= plane;
             }
         }
+        collision
     }
 
     fn u8_to_bools(val: &u8) -> [bool; 8] {
         std::array::from_fn(|i| 1 << (7 - i) & val != 0) 
     }
 
-    pub fn to_buffer(&self) -> Vec<u32> { // TODO
-        let mut vec = Vec::<u32>::new();
-        for row in self.screen {
-            for val in row {
-                if val {
-                    vec.push(0xFF0000);
+    /// Moves the selected planes by `dx` columns and `dy` rows,
+    /// what comes in from the edges is blank.
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let (width, height) = (self.width() as isize, self.height() as isize);
+        let planes = self.planes;
+        let old = self.screen.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = (x - dx, y - dy);
+                let moved = if (0..width).contains(&sx) && (0..height).contains(&sy) {
+                    old[(sy * width + sx) as usize] & planes
                 } else {
-                    vec.push(0);
-                }
+                    0
+                };
+                let pixel = &mut self.screen[(y * width + x) as usize];
+                *pixel = (*pixel & !planes) | moved;
+            }
+        }
+    }
+
+    /// Moves everything down by `rows`, the top rows become blank.
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll(0, rows as isize);
+    }
+
+    /// Moves everything up by `rows`, the bottom rows become blank (XO-CHIP).
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll(0, -(rows as isize));
+    }
+
+    /// Moves everything right by `columns`, the leftmost columns become blank.
+    pub fn scroll_right(&mut self, columns: usize) {
+        self.scroll(columns as isize, 0);
+    }
+
+    /// Moves everything left by `columns`, the rightmost columns become blank.
+    pub fn scroll_left(&mut self, columns: usize) {
+        self.scroll(-(columns as isize), 0);
+    }
+
+    /// `width() * height()` colours, row after row.
+    pub fn to_buffer(&self, palette: Palette) -> Vec<u32> {
+        self.screen.iter().map(|planes| palette.color(*planes)).collect()
+    }
+
+    /// One line per row: `.` for pixels that are off, `#` for the ones lit in the
+    /// first plane, `+` in the second and `@` in both.
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity((self.width() + 1) * self.height());
+        for row in self.screen.chunks(self.width()) {
+            for planes in row {
+                text.push(['.', '#', '+', '@'][(*planes & 0b11) as usize]);
             }
+            text.push('\n');
         }
-        vec
-    }
-
-    pub fn get_keys(&self) {
-        println!("CIAO Keys: {:?}", self.window.get_keys());
-    }   
-    
-    
-}
-
-pub fn load(filepath: &str) -> Vec<Data> {
-    std::fs::read(filepath).unwrap_or_else(|_| panic!("unable to read {filepath}"))
-}
-
-pub fn u8_to_key(key: u8) -> minifb::Key {
-    match key {
-        0x1 => Key::Key1,
-        0x2 => Key::Key2,
-        0x3 => Key::Key3,
-        0xC => Key::Key4,
-        0x4 => Key::Q,
-        0x5 => Key::W,
-        0x6 => Key::E,
-        0xD => Key::R,
-        0x7 => Key::A,
-        0x8 => Key::S,
-        0x9 => Key::D,
-        0xE => Key::F,
-        0xA => Key::Z,
-        0x0 => Key::X,
-        0xB => Key::C,
-        0xF => Key::V,
-        _ => panic!("unknown key!")
-    }
-}
-
-pub fn key_to_u8(key: minifb::Key) -> u8 {
-    match key {
-        Key::Key1 => 0x1, 
-        Key::Key2 => 0x2, 
-        Key::Key3 => 0x3, 
-        Key::Key4 => 0xC, 
-        Key::Q => 0x4, 
-        Key::W => 0x5, 
-        Key::E => 0x6, 
-        Key::R => 0xD, 
-        Key::A => 0x7, 
-        Key::S => 0x8, 
-        Key::D => 0x9, 
-        Key::F => 0xE, 
-        Key::Z => 0xA, 
-        Key::X => 0x0, 
-        Key::C => 0xB, 
-        Key::V => 0xF, 
-        _ => panic!("unknown key!")
+        text
+    }
+}
+
+/// Reads a ROM image, refusing files that are empty or do not fit in memory.
+pub fn load(filepath: &str) -> std::io::Result<Vec<Data>> {
+    let rom = std::fs::read(filepath)?;
+    if rom.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "the file is empty"));
+    }
+    if rom.len() > MAX_ROM_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("the file is {} bytes long, at most {MAX_ROM_SIZE} fit in memory", rom.len()),
+        ));
     }
+    Ok(rom)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(screen: &Screen) -> usize {
+        screen.to_text().chars().filter(|c| !matches!(c, '.' | '\n')).count()
+    }
+
+    #[test]
+    fn draw_xors_pixels() {
+        let mut screen = Screen::new();
+        assert!(!screen.draw(0, 0, &[0b1100_0000]));
+        assert_eq!(screen.get(0, 0), Some(true));
+        assert_eq!(screen.get(1, 0), Some(true));
+
+        screen.draw(1, 0, &[0b1100_0000]);
+        assert_eq!(screen.get(0, 0), Some(true));
+        assert_eq!(screen.get(1, 0), Some(false));
+        assert_eq!(screen.get(2, 0), Some(true));
+    }
+
+    #[test]
+    fn drawing_twice_erases_the_sprite() {
+        let mut screen = Screen::new();
+        let sprite = [0xF0, 0x90, 0x90, 0x90, 0xF0];
+        assert!(!screen.draw(10, 5, &sprite));
+        assert_eq!(lit(&screen), 14);
+        assert!(screen.draw(10, 5, &sprite));
+        assert_eq!(lit(&screen), 0);
+    }
+
+    #[test]
+    fn collision_only_when_a_pixel_is_turned_off() {
+        let mut screen = Screen::new();
+        screen.draw(0, 0, &[0b1000_0000]);
+        assert!(!screen.draw(1, 0, &[0b1000_0000]));
+        assert!(screen.draw(0, 0, &[0b1000_0000]));
+    }
+
+    #[test]
+    fn starting_coordinates_wrap() {
+        let mut screen = Screen::new();
+        screen.draw(WIDTH + 3, HEIGHT + 2, &[0b1000_0000]);
+        assert_eq!(screen.get(3, 2), Some(true));
+        assert_eq!(lit(&screen), 1);
+    }
+
+    #[test]
+    fn sprites_are_clipped_by_default() {
+        let mut screen = Screen::new();
+        assert_eq!(screen.edge(), SpriteEdge::Clip);
+        screen.draw(WIDTH - 4, HEIGHT - 1, &[0xFF, 0xFF]);
+        assert_eq!(lit(&screen), 4);
+        assert_eq!(screen.get(0, 0), Some(false));
+    }
+
+    #[test]
+    fn sprites_wrap_when_asked() {
+        let mut screen = Screen::new();
+        screen.set_edge(SpriteEdge::Wrap);
+        screen.draw(WIDTH - 4, HEIGHT - 1, &[0xFF, 0xFF]);
+        assert_eq!(lit(&screen), 16);
+        assert_eq!(screen.get(WIDTH - 1, HEIGHT - 1), Some(true));
+        assert_eq!(screen.get(0, HEIGHT - 1), Some(true));
+        assert_eq!(screen.get(3, 0), Some(true));
+        assert_eq!(screen.get(4, 0), Some(false));
+    }
+
+    #[test]
+    fn large_sprites_are_16_pixels_wide() {
+        let mut screen = Screen::new();
+        screen.set_hires(true);
+        assert!(!screen.draw_large(0, 0, &[0xFF; 32]));
+        assert_eq!(lit(&screen), 256);
+        assert_eq!(screen.get(15, 15), Some(true));
+        assert_eq!(screen.get(16, 0), Some(false));
+        assert!(screen.draw_large(8, 8, &[0x80, 0x00]));
+    }
+
+    #[test]
+    fn switching_resolution_clears_the_screen() {
+        let mut screen = Screen::new();
+        screen.draw(0, 0, &[0xFF]);
+        screen.set_hires(true);
+        assert_eq!((screen.width(), screen.height()), (HIRES_WIDTH, HIRES_HEIGHT));
+        assert_eq!(lit(&screen), 0);
+        assert_eq!(screen.to_buffer(Palette::default()).len(), HIRES_WIDTH * HIRES_HEIGHT);
+        screen.draw(HIRES_WIDTH - 1, HIRES_HEIGHT - 1, &[0x80]);
+        assert_eq!(screen.get(HIRES_WIDTH - 1, HIRES_HEIGHT - 1), Some(true));
+    }
+
+    #[test]
+    fn scrolling_moves_pixels_and_blanks_the_rest() {
+        let mut screen = Screen::new();
+        screen.draw(0, 0, &[0x80]);
+        screen.scroll_down(3);
+        assert_eq!(screen.get(0, 3), Some(true));
+        screen.scroll_right(4);
+        assert_eq!(screen.get(4, 3), Some(true));
+        screen.scroll_left(4);
+        screen.scroll_left(4);
+        assert_eq!(lit(&screen), 0);
+    }
+
+    #[test]
+    fn sprites_are_drawn_on_the_selected_planes() {
+        let mut screen = Screen::new();
+        screen.set_planes(0b10);
+        screen.draw(0, 0, &[0x80]);
+        assert_eq!(screen.pixel(0, 0), Some(0b10));
+
+        screen.set_planes(0b11);
+        assert!(screen.draw(0, 0, &[0xC0, 0xC0]));
+        assert_eq!(screen.pixel(0, 0), Some(0b01));
+        assert_eq!(screen.pixel(1, 0), Some(0b11));
+    }
+
+    #[test]
+    fn clear_and_scroll_only_touch_the_selected_planes() {
+        let mut screen = Screen::new();
+        screen.set_planes(0b11);
+        screen.draw(0, 0, &[0x80, 0x80]);
+        screen.set_planes(0b10);
+        screen.scroll_right(4);
+        assert_eq!(screen.pixel(0, 0), Some(0b01));
+        assert_eq!(screen.pixel(4, 0), Some(0b10));
+        screen.clear();
+        assert_eq!(screen.pixel(4, 0), Some(0));
+        assert_eq!(screen.pixel(0, 0), Some(0b01));
+    }
+
+    #[test]
+    fn palette_composites_the_planes() {
+        let palette: Palette = "ff0000,000000,00ff00,ffffff".parse().unwrap();
+        assert_eq!([0, 1, 2, 3].map(|p| palette.color(p)), [0x000000, 0xFF0000, 0x00FF00, 0xFFFFFF]);
+        assert!("ff0000,000000,00ff00".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn clear_turns_everything_off() {
+        let mut screen = Screen::new();
+        screen.draw(0, 0, &[0xFF; 15]);
+        screen.clear();
+        assert_eq!(lit(&screen), 0);
+    }
+}
@@ -0,0 +1,308 @@
+use std::{fmt, fs};
+
+use c8::types::Data;
+use minifb::Key;
+
+/// Which keyboard keys stand for each of the 16 keys of the CHIP-8 keypad.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: [Vec<Key>; 16],
+}
+
+/// An error in a keymap file, `line` starting at 1.
+#[derive(Debug, PartialEq)]
+pub struct KeymapErr {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KeymapErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+/// The keys F1 to F9, for save state slots 1 to 9.
+pub const SLOT_KEYS: [Key; 9] = [Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9];
+
+/// The other keys the window keeps for itself: Escape quits, Tab fast-forwards,
+/// Backspace rewinds, F10 pauses, and Shift turns a slot key into a save.
+pub const HOTKEYS: [Key; 6] = [Key::Escape, Key::Tab, Key::Backspace, Key::F10, Key::LeftShift, Key::RightShift];
+
+/// True for the keys the window keeps for itself, which cannot be bound.
+pub fn is_reserved(key: Key) -> bool {
+    SLOT_KEYS.contains(&key) || HOTKEYS.contains(&key)
+}
+
+/// The CHIP-8 keys as laid out on the COSMAC VIP keypad, row by row.
+const PAD: [[Data; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::qwerty()
+    }
+}
+
+impl Keymap {
+    /// A keymap with no key bound.
+    pub fn new() -> Keymap {
+        Keymap { bindings: Default::default() }
+    }
+
+    /// The keypad on the left of the keyboard: 1234, QWER, ASDF and ZXCV.
+    pub fn qwerty() -> Keymap {
+        Keymap::from_rows([
+            [Key::Key1, Key::Key2, Key::Key3, Key::Key4],
+            [Key::Q, Key::W, Key::E, Key::R],
+            [Key::A, Key::S, Key::D, Key::F],
+            [Key::Z, Key::X, Key::C, Key::V],
+        ])
+    }
+
+    /// The same physical keys on an AZERTY keyboard.
+    pub fn azerty() -> Keymap {
+        Keymap::from_rows([
+            [Key::Key1, Key::Key2, Key::Key3, Key::Key4],
+            [Key::A, Key::Z, Key::E, Key::R],
+            [Key::Q, Key::S, Key::D, Key::F],
+            [Key::W, Key::X, Key::C, Key::V],
+        ])
+    }
+
+    /// The same physical keys on a Dvorak keyboard.
+    pub fn dvorak() -> Keymap {
+        Keymap::from_rows([
+            [Key::Key1, Key::Key2, Key::Key3, Key::Key4],
+            [Key::Apostrophe, Key::Comma, Key::Period, Key::P],
+            [Key::A, Key::O, Key::E, Key::U],
+            [Key::Semicolon, Key::Q, Key::J, Key::K],
+        ])
+    }
+
+    /// One of the built-in layouts: `qwerty`, `azerty` or `dvorak`.
+    pub fn layout(name: &str) -> Option<Keymap> {
+        match name.to_ascii_lowercase().as_str() {
+            "qwerty" => Some(Keymap::qwerty()),
+            "azerty" => Some(Keymap::azerty()),
+            "dvorak" => Some(Keymap::dvorak()),
+            _ => None,
+        }
+    }
+
+    fn from_rows(rows: [[Key; 4]; 4]) -> Keymap {
+        let mut keymap = Keymap::new();
+        for (pad, row) in PAD.iter().zip(rows) {
+            for (key, physical) in pad.iter().zip(row) {
+                keymap.bind(*key, physical);
+            }
+        }
+        keymap
+    }
+
+    /// Adds a keyboard key to the ones standing for `key`.
+    pub fn bind(&mut self, key: Data, physical: Key) {
+        let keys = &mut self.bindings[key as usize & 0xF];
+        if !keys.contains(&physical) {
+            keys.push(physical);
+        }
+    }
+
+    /// The keyboard keys bound to `key`.
+    pub fn keys(&self, key: Data) -> &[Key] {
+        self.bindings.get(key as usize).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn load(path: &str) -> Result<Keymap, KeymapErr> {
+        let text = fs::read_to_string(path).map_err(|e| KeymapErr { line: 0, message: e.to_string() })?;
+        Keymap::parse(&text)
+    }
+
+    /// Reads lines like `A = Z, NumPad0`: a CHIP-8 key in hexadecimal, then the
+    /// names of the keyboard keys bound to it. `#` starts a comment.
+    pub fn parse(text: &str) -> Result<Keymap, KeymapErr> {
+        let mut keymap = Keymap::new();
+        for (index, line) in text.lines().enumerate() {
+            let err = |message: String| KeymapErr { line: index + 1, message };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, physical) = line
+                .split_once('=')
+                .ok_or_else(|| err("expected `<key> = <keyboard keys>`".to_string()))?;
+            let key = key.trim();
+            let key = match Data::from_str_radix(key, 16) {
+                Ok(value) if value < 16 && key.len() == 1 => value,
+                _ => return Err(err(format!("`{key}` is not a key from 0 to F"))),
+            };
+            for name in physical.split(',').map(str::trim) {
+                let physical = key_from_name(name).ok_or_else(|| err(format!("unknown keyboard key `{name}`")))?;
+                if is_reserved(physical) {
+                    return Err(err(format!("`{name}` is a hotkey and cannot be bound")));
+                }
+                keymap.bind(key, physical);
+            }
+        }
+        Ok(keymap)
+    }
+}
+
+/// The keyboard key with the same name as the `minifb::Key` variant, ignoring
+/// case. Digits can be written without the `Key` prefix.
+fn key_from_name(name: &str) -> Option<Key> {
+    let name = name.to_ascii_lowercase();
+    let key = match name.as_str() {
+        "0" | "key0" => Key::Key0,
+        "1" | "key1" => Key::Key1,
+        "2" | "key2" => Key::Key2,
+        "3" | "key3" => Key::Key3,
+        "4" | "key4" => Key::Key4,
+        "5" | "key5" => Key::Key5,
+        "6" | "key6" => Key::Key6,
+        "7" | "key7" => Key::Key7,
+        "8" | "key8" => Key::Key8,
+        "9" | "key9" => Key::Key9,
+        "a" => Key::A,
+        "b" => Key::B,
+        "c" => Key::C,
+        "d" => Key::D,
+        "e" => Key::E,
+        "f" => Key::F,
+        "g" => Key::G,
+        "h" => Key::H,
+        "i" => Key::I,
+        "j" => Key::J,
+        "k" => Key::K,
+        "l" => Key::L,
+        "m" => Key::M,
+        "n" => Key::N,
+        "o" => Key::O,
+        "p" => Key::P,
+        "q" => Key::Q,
+        "r" => Key::R,
+        "s" => Key::S,
+        "t" => Key::T,
+        "u" => Key::U,
+        "v" => Key::V,
+        "w" => Key::W,
+        "x" => Key::X,
+        "y" => Key::Y,
+        "z" => Key::Z,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "escape" => Key::Escape,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "apostrophe" => Key::Apostrophe,
+        "backquote" => Key::Backquote,
+        "backslash" => Key::Backslash,
+        "comma" => Key::Comma,
+        "equal" => Key::Equal,
+        "leftbracket" => Key::LeftBracket,
+        "rightbracket" => Key::RightBracket,
+        "minus" => Key::Minus,
+        "period" => Key::Period,
+        "semicolon" => Key::Semicolon,
+        "slash" => Key::Slash,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "end" => Key::End,
+        "enter" => Key::Enter,
+        "home" => Key::Home,
+        "insert" => Key::Insert,
+        "pagedown" => Key::PageDown,
+        "pageup" => Key::PageUp,
+        "space" => Key::Space,
+        "tab" => Key::Tab,
+        "leftshift" => Key::LeftShift,
+        "rightshift" => Key::RightShift,
+        "leftctrl" => Key::LeftCtrl,
+        "rightctrl" => Key::RightCtrl,
+        "leftalt" => Key::LeftAlt,
+        "rightalt" => Key::RightAlt,
+        "numpad0" => Key::NumPad0,
+        "numpad1" => Key::NumPad1,
+        "numpad2" => Key::NumPad2,
+        "numpad3" => Key::NumPad3,
+        "numpad4" => Key::NumPad4,
+        "numpad5" => Key::NumPad5,
+        "numpad6" => Key::NumPad6,
+        "numpad7" => Key::NumPad7,
+        "numpad8" => Key::NumPad8,
+        "numpad9" => Key::NumPad9,
+        "numpaddot" => Key::NumPadDot,
+        "numpadslash" => Key::NumPadSlash,
+        "numpadasterisk" => Key::NumPadAsterisk,
+        "numpadminus" => Key::NumPadMinus,
+        "numpadplus" => Key::NumPadPlus,
+        "numpadenter" => Key::NumPadEnter,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_key_can_have_several_bindings() {
+        let keymap = Keymap::parse("# arrows and numpad\n5 = Up, NumPad8\n8 = down\n").unwrap();
+        assert_eq!(keymap.keys(0x5), [Key::Up, Key::NumPad8]);
+        assert_eq!(keymap.keys(0x8), [Key::Down]);
+        assert!(keymap.keys(0x0).is_empty());
+    }
+
+    #[test]
+    fn errors_report_the_line() {
+        assert_eq!(Keymap::parse("1 = 1\n\nG = A").unwrap_err().line, 3);
+        assert_eq!(Keymap::parse("1 = 1\n2 = Escape").unwrap_err().line, 2);
+        assert_eq!(Keymap::parse("1 1").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn layouts_bind_the_same_physical_keys() {
+        assert_eq!(Keymap::azerty().keys(0x4), [Key::A]);
+        assert_eq!(Keymap::dvorak().keys(0xF), [Key::K]);
+        assert_eq!(Keymap::layout("QWERTY"), Some(Keymap::qwerty()));
+    }
+
+    #[test]
+    fn hotkeys_cannot_be_bound() {
+        for name in ["Escape", "Tab", "Backspace", "F1", "F9", "F10", "LeftShift"] {
+            let err = Keymap::parse(&format!("1 = {name}")).unwrap_err();
+            assert_eq!(err.message, format!("`{name}` is a hotkey and cannot be bound"));
+        }
+        assert!(Keymap::parse("1 = F11, P").is_ok());
+    }
+
+    #[test]
+    fn layouts_leave_the_hotkeys_alone() {
+        for layout in [Keymap::qwerty(), Keymap::azerty(), Keymap::dvorak()] {
+            for key in 0..16 {
+                assert!(!layout.keys(key).iter().any(|k| is_reserved(*k)), "{layout:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! A CHIP-8 interpreter that does not depend on any particular frontend.
+//!
+//! The host provides a [`io::Display`], an [`io::Input`] and an [`io::Audio`]
+//! implementation and drives the [`Chip`] by calling [`Chip::cycle`]. The
+//! [`headless::Headless`] frontend keeps everything in memory instead.
+
+pub mod asm;
+pub mod audio;
+pub mod c8;
+pub mod mem;
+pub mod types;
+pub mod stack;
+pub mod err;
+pub mod timer;
+pub mod trace;
+pub mod io;
+pub mod movie;
+pub mod decoder;
+pub mod disasm;
+pub mod headless;
+pub mod quirks;
+pub mod random;
+pub mod rewind;
+pub mod scheduler;
+pub mod state;
+
+pub use crate::c8::Chip;
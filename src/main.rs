@@ -1,25 +1,312 @@
-pub mod c8;
-pub mod mem;
-pub mod types;
-pub mod stack;
-pub mod err;
-pub mod timer;
-pub mod io;
-mod decoder;
+#[cfg(feature = "beeper")]
+mod beeper;
+mod cli;
+mod debugger;
+mod desktop;
+mod keymap;
 
-use std::{thread, time::Duration};
+use std::{env, fs, process, thread, time::Instant};
+use std::io::BufWriter;
+use std::path::Path;
 
 use c8::Chip;
-//use io::Screen;
-use minifb::{Key};
+use c8::audio::WavWriter;
+use c8::err::C8Err;
+use c8::headless::Headless;
+use c8::io::{self, Audio, Input, NullAudio};
+use c8::movie::Movie;
+use c8::rewind::Rewind;
+use c8::scheduler::{Scheduler, FRAME};
+use c8::trace::Tracer;
+use c8::types::Data;
+use cli::{Args, CliErr};
+use debugger::Debugger;
+use desktop::{Desktop, Hotkey};
+use keymap::Keymap;
 
 fn main() {
-    let mut chip = Chip::new();
-    chip.load("run/ibm.ch8");
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(CliErr::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        },
+        Err(e) => {
+            eprintln!("c8: {}\n\n{}", e.message(), cli::USAGE);
+            process::exit(2);
+        },
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let rom = match io::load(&args.rom) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("c8: unable to load {}: {e}", args.rom);
+            process::exit(1);
+        },
+    };
+
+    if args.headless {
+        run_headless(&args, &rom);
+    } else {
+        run_desktop(&args, &rom);
+    }
+}
+
+/// Runs as fast as possible for the requested number of frames, then dumps the screen.
+fn run_headless(args: &Args, rom: &[Data]) {
+    let headless = Headless::new();
+    let movie = args.replay.as_deref().map(|path| load_movie(path, rom));
+    let input: Box<dyn Input> = match &movie {
+        Some(movie) => Box::new(movie.player()),
+        None => Box::new(headless.clone()),
+    };
+    let mut chip = match &args.wav {
+        Some(path) => {
+            let wav = fs::File::create(path)
+                .and_then(|file| WavWriter::new(BufWriter::new(file), args.tone));
+            match wav {
+                Ok(wav) => Chip::new(Box::new(headless.clone()), input, Box::new(wav)),
+                Err(e) => {
+                    eprintln!("c8: unable to write {path}: {e}");
+                    process::exit(1);
+                },
+            }
+        },
+        None => Chip::new(Box::new(headless.clone()), input, Box::new(headless.clone())),
+    };
+    chip.set_quirks(args.quirks);
+    chip.set_instructions_per_frame(args.per_frame);
+    if let Some(movie) = &movie {
+        movie.apply(&mut chip);
+    }
+    if let Err(e) = chip.load(rom) {
+        crash(&mut chip, e);
+    }
     chip.start();
-    while chip.screen.window.is_open() && !chip.screen.window.is_key_down(Key::Escape) {
-        chip.cycle();
-        thread::sleep(Duration::from_millis(10));
-        chip.screen.window.update_with_buffer(&chip.screen.to_buffer(), io::WIDTH, io::HEIGHT).unwrap();
+    trace(args, &mut chip);
+
+    let frames = args.frames.or(movie.as_ref().map(|movie| movie.len() as u64));
+    for frame in 0..frames.unwrap_or_default() {
+        let running = run_frame(&mut chip);
+        chip.present();
+        if let Some(movie) = &movie {
+            verify(args, movie, frame as usize, &mut chip);
+        }
+        if !running {
+            break;
+        }
+    }
+
+    let text = headless.frame().to_text();
+    match &args.dump {
+        Some(path) => {
+            if let Err(e) = fs::write(path, text) {
+                eprintln!("c8: unable to write {path}: {e}");
+                process::exit(1);
+            }
+        },
+        None => print!("{text}"),
+    }
+}
+
+fn run_desktop(args: &Args, rom: &[Data]) {
+    let keymap = match args.keymap.as_deref() {
+        None => Keymap::default(),
+        Some(name) => match Keymap::layout(name).map_or_else(|| Keymap::load(name), Ok) {
+            Ok(keymap) => keymap,
+            Err(e) => {
+                eprintln!("c8: unable to load the keymap {name}: {e}");
+                process::exit(1);
+            },
+        },
+    };
+    let desktop = match Desktop::new(args.scale, args.palette, keymap) {
+        Ok(desktop) => desktop,
+        Err(e) => {
+            eprintln!("c8: unable to open the window: {e}");
+            process::exit(1);
+        },
+    };
+
+    let replay = args.replay.as_deref().map(|path| load_movie(path, rom));
+    let input = match &replay {
+        Some(movie) => Box::new(movie.player()),
+        None => desktop.input(),
+    };
+    let mut chip = Chip::new(desktop.display(), input, audio(args));
+    chip.set_quirks(args.quirks);
+    chip.set_instructions_per_frame(args.per_frame);
+    if let Some(movie) = &replay {
+        movie.apply(&mut chip);
+    }
+    if let Err(e) = chip.load(rom) {
+        crash(&mut chip, e);
+    }
+    chip.start();
+    trace(args, &mut chip);
+
+    if args.debug {
+        let period = FRAME / chip.instructions_per_frame();
+        chip.present();
+        Debugger::new().run(&mut chip, |chip| {
+            thread::sleep(period);
+            chip.present();
+            desktop.is_open()
+        });
+        return;
+    }
+    // Going back in time or loading a state would make a movie impossible to replay.
+    let mut recording = args.record.as_ref().map(|_| Movie::new(&chip, rom));
+    let filming = recording.is_some() || replay.is_some();
+    let mut rewind = Rewind::new(if filming { 0 } else { args.rewind_depth }, args.rewind_budget);
+    let mut scheduler = Scheduler::new(Instant::now(), args.fast_forward);
+    let mut frame = 0;
+    'window: while desktop.is_open() {
+        scheduler.wait();
+        let due = scheduler.tick(Instant::now());
+        if due == 0 {
+            continue;
+        }
+        scheduler.set_fast(desktop.is_fast_forwarding());
+        // While rewinding, go back one frame per frame instead of executing.
+        let rewinding = !filming && desktop.is_rewinding();
+        for _ in 0..scheduler.frames_to_run(due) {
+            if rewinding {
+                rewind.step_back(&mut chip);
+                continue;
+            }
+            if !run_frame(&mut chip) {
+                break 'window;
+            }
+            rewind.capture(&chip);
+            if let Some(movie) = &mut recording {
+                movie.record(&chip);
+            }
+            if let Some(movie) = &replay {
+                verify(args, movie, frame, &mut chip);
+            }
+            frame += 1;
+        }
+        chip.present();
+        for hotkey in desktop.hotkeys() {
+            match hotkey {
+                Hotkey::Pause => {
+                    scheduler.set_paused(!scheduler.is_paused());
+                    eprintln!("c8: {}", if scheduler.is_paused() { "paused" } else { "resumed" });
+                },
+                _ if filming => {},
+                Hotkey::SaveState(slot) => use_slot(&mut chip, &args.rom, slot, true),
+                Hotkey::LoadState(slot) => use_slot(&mut chip, &args.rom, slot, false),
+            }
+        }
+    }
+
+    if let (Some(path), Some(movie)) = (&args.record, recording) {
+        match fs::write(path, movie.to_bytes()) {
+            Ok(()) => eprintln!("c8: recorded {} frames to {path}", movie.len()),
+            Err(e) => {
+                eprintln!("c8: unable to write {path}: {e}");
+                process::exit(1);
+            },
+        }
+    }
+}
+
+fn load_movie(path: &str, rom: &[Data]) -> Movie {
+    let movie = fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| Movie::from_bytes(&bytes).map_err(|e| e.to_string()));
+    match movie {
+        Ok(movie) => {
+            if !movie.is_for(rom) {
+                eprintln!("c8: warning: {path} was recorded with another ROM");
+            }
+            movie
+        },
+        Err(e) => {
+            eprintln!("c8: unable to load the movie {path}: {e}");
+            process::exit(1);
+        },
+    }
+}
+
+/// With `--verify`, stops at the first replayed frame whose screen is not the recorded one.
+fn verify(args: &Args, movie: &Movie, frame: usize, chip: &mut Chip) {
+    if !args.verify || frame >= movie.len() {
+        return;
+    }
+    if !movie.matches(frame, chip) {
+        eprintln!("c8: the replay diverged from the movie at frame {frame}");
+        chip.set_tracer(None);
+        process::exit(1);
+    }
+    if frame + 1 == movie.len() {
+        eprintln!("c8: the replay matched all {} frames of the movie", movie.len());
+    }
+}
+
+fn trace(args: &Args, chip: &mut Chip) {
+    let Some(path) = &args.trace else { return };
+    match fs::File::create(path) {
+        Ok(file) => chip.set_tracer(Some(Tracer::new(Box::new(BufWriter::new(file))))),
+        Err(e) => {
+            eprintln!("c8: unable to write {path}: {e}");
+            process::exit(1);
+        },
+    }
+}
+
+/// Runs the instructions of one frame, false once the program has halted.
+fn run_frame(chip: &mut Chip) -> bool {
+    if let Err(e) = chip.run_frames(1) {
+        crash(chip, e);
     }
+    !chip.is_halted()
+}
+
+/// Saves or restores the machine, slots being files next to the ROM: `rom.1.state`...
+fn use_slot(chip: &mut Chip, rom: &str, slot: u8, save: bool) {
+    let path = Path::new(rom).with_extension(format!("{slot}.state"));
+    let result = if save {
+        fs::write(&path, chip.save_state()).map_err(|e| e.to_string())
+    } else {
+        fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|state| chip.load_state(&state).map_err(|e| e.to_string()))
+    };
+    let path = path.display();
+    match result {
+        Ok(()) if save => eprintln!("c8: saved slot {slot} to {path}"),
+        Ok(()) => eprintln!("c8: loaded slot {slot} from {path}"),
+        Err(e) => eprintln!("c8: slot {slot}: {path}: {e}"),
+    }
+}
+
+#[cfg(feature = "beeper")]
+fn audio(args: &Args) -> Box<dyn Audio> {
+    if args.mute {
+        return Box::new(NullAudio);
+    }
+    match beeper::Beeper::new(args.tone) {
+        Ok(beeper) => Box::new(beeper),
+        Err(e) => {
+            eprintln!("c8: unable to play sound: {e}");
+            Box::new(NullAudio)
+        },
+    }
+}
+
+/// Built without the `beeper` feature, the desktop frontend is silent.
+#[cfg(not(feature = "beeper"))]
+fn audio(_args: &Args) -> Box<dyn Audio> {
+    Box::new(NullAudio)
+}
+
+/// Reports why the program stopped along with the state of the machine.
+fn crash(chip: &mut Chip, e: C8Err) -> ! {
+    eprint!("c8: the program crashed: {e}\n{}", chip.dump());
+    // Flushes the trace, which matters most when something went wrong.
+    chip.set_tracer(None);
+    process::exit(1);
 }
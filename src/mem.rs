@@ -1,13 +1,29 @@
 use crate::{types::Data, err::C8Err};
+
+/// 64 KiB, as on XO-CHIP. Older programs only use the first 4 KiB.
+pub const SIZE : usize = 0x10000;
+
+/// Where the built-in hexadecimal font is stored, each glyph is 5 bytes long.
+const FONT_ADDRESS : usize = 0x50;
+
+/// Where the SUPER-CHIP high resolution font is stored, each glyph is 10 bytes long.
+const LARGE_FONT_ADDRESS : usize = 0xA0;
+
 #[derive(Debug)]
 pub struct Memory {
     pub vector : Vec<Data>
 }
 
+impl Default for Memory {
+    fn default() -> Self {
+        Memory::new()
+    }
+}
+
 impl Memory {
     pub fn new() -> Memory {
         Memory {
-            vector : vec![0; 4096]
+            vector : vec![0; SIZE]
         }
     }
 
@@ -31,26 +47,59 @@ impl Memory {
             0xF0, 0x80, 0xF0, 0x80, 0x80  // F
         ];
 
-        for i in 0..font.len() {
-            self.write(font[i], (0x1 + i));
-        }
+        self.vector[FONT_ADDRESS..FONT_ADDRESS + font.len()].copy_from_slice(&font);
+
+        let large_font: [u8; 160] = [
+            0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+            0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+            0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+            0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+            0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+            0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+            0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+            0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+            0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+            0x3C, 0x7E, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, // A
+            0xFC, 0xFE, 0xC3, 0xC3, 0xFE, 0xFE, 0xC3, 0xC3, 0xFE, 0xFC, // B
+            0x3C, 0x7E, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0x7E, 0x3C, // C
+            0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xFF, 0xFF, // E
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xC0, 0xC0  // F
+        ];
+
+        self.vector[LARGE_FONT_ADDRESS..LARGE_FONT_ADDRESS + large_font.len()].copy_from_slice(&large_font);
     }
 
     pub fn get_font(&self, ch : u8) -> usize {
-        (0x1 * ch) as usize
+        FONT_ADDRESS + 5 * ch as usize
+    }
+
+    pub fn get_large_font(&self, ch : u8) -> usize {
+        LARGE_FONT_ADDRESS + 10 * ch as usize
     }
 
     pub fn get(&self, index: usize) -> Result<Data, C8Err> {
         match self.vector.get(index) {
             Some(value) => Ok(*value),
-            None => Err(C8Err::MemoryUnaccessible)
+            None => Err(C8Err::ReadOutOfRange { address: index })
         }
     }
 
-    pub fn write(&mut self, v:Data, index:usize) {
+    pub fn write(&mut self, v:Data, index:usize) -> Result<(), C8Err> {
         match self.vector.get_mut(index) {
-            Some(value) => *value = v,
-            None => panic!("mem unaccessible")
+            Some(value) => {
+                *value = v;
+                Ok(())
+            },
+            None => Err(C8Err::WriteOutOfRange { address: index })
         }
     }
-}
\ No newline at end of file
+
+    /// `len` bytes starting at `index`.
+    pub fn slice(&self, index: usize, len: usize) -> Result<&[Data], C8Err> {
+        let end = index.checked_add(len).filter(|end| *end <= SIZE)
+            .ok_or(C8Err::ReadOutOfRange { address: index.saturating_add(len).saturating_sub(1) })?;
+        Ok(&self.vector[index..end])
+    }
+}
@@ -0,0 +1,231 @@
+use crate::c8::Chip;
+use crate::io::{Input, Keypad, Screen};
+use crate::quirks::Quirks;
+use crate::state::{self, Reader, StateErr, Writer};
+use crate::types::Data;
+
+/// Every movie starts with these bytes, followed by the save state header.
+pub const MAGIC: [u8; 4] = *b"C8MV";
+
+pub const VERSION: u16 = 1;
+
+/// What a frame of a movie remembers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    /// The keys the machine saw during the frame.
+    pub keys: Keypad,
+    /// `screen_hash` of the screen at the end of the frame.
+    pub screen: u32,
+}
+
+/// A recorded run: everything needed to replay it exactly, the keys pressed at
+/// each frame, and the screen of each frame to check that the replay did not
+/// diverge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    seed: u64,
+    quirks: Quirks,
+    per_frame: u32,
+    rom: u32,
+    frames: Vec<Frame>,
+}
+
+impl Movie {
+    /// Starts a movie of `chip`, which must not have executed anything yet.
+    pub fn new(chip: &Chip, rom: &[Data]) -> Movie {
+        Movie {
+            seed: chip.random_state(),
+            quirks: chip.quirks(),
+            per_frame: chip.instructions_per_frame(),
+            rom: state::crc32(rom),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Records the frame `chip` just finished.
+    pub fn record(&mut self, chip: &Chip) {
+        self.frames.push(Frame { keys: chip.keypad(), screen: screen_hash(chip.screen()) });
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Whether the movie was recorded with this ROM.
+    pub fn is_for(&self, rom: &[Data]) -> bool {
+        self.rom == state::crc32(rom)
+    }
+
+    /// Gives `chip` the seed, quirks and speed the movie was recorded with.
+    pub fn apply(&self, chip: &mut Chip) {
+        chip.set_random_state(self.seed);
+        chip.set_quirks(self.quirks);
+        chip.set_instructions_per_frame(self.per_frame);
+    }
+
+    /// An input that presses the recorded keys, one frame per poll.
+    pub fn player(&self) -> Player {
+        Player { keys: self.frames.iter().map(|frame| frame.keys).collect(), next: 0 }
+    }
+
+    /// Whether the screen of `chip` is the one recorded at the end of `frame`.
+    /// Frames past the end of the movie never match.
+    pub fn matches(&self, frame: usize, chip: &Chip) -> bool {
+        self.frames.get(frame).is_some_and(|recorded| recorded.screen == screen_hash(chip.screen()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::new();
+        w.u64(self.seed);
+        w.u8(self.quirks.to_bits());
+        w.u32(self.per_frame);
+        w.u32(self.rom);
+        w.u32(self.frames.len() as u32);
+        for frame in &self.frames {
+            w.u16(frame.keys.bits());
+            w.u32(frame.screen);
+        }
+        w.finish_as(MAGIC, VERSION)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Movie, StateErr> {
+        let mut r = Reader::open_as(bytes, MAGIC, VERSION)?;
+        let seed = r.u64()?;
+        let quirks = Quirks::from_bits(r.u8()?);
+        let per_frame = r.u32()?;
+        if per_frame == 0 {
+            return Err(StateErr::Invalid { what: "no instruction per frame" });
+        }
+        let rom = r.u32()?;
+        let len = r.u32()? as usize;
+        let mut frames = Vec::with_capacity(len.min(bytes.len() / 6));
+        for _ in 0..len {
+            frames.push(Frame { keys: Keypad::from_bits(r.u16()?), screen: r.u32()? });
+        }
+        r.finish()?;
+        Ok(Movie { seed, quirks, per_frame, rom, frames })
+    }
+}
+
+/// CRC-32 of the resolution, the planes and the pixels.
+pub fn screen_hash(screen: &Screen) -> u32 {
+    let mut bytes = vec![screen.is_hires() as u8, screen.planes()];
+    bytes.extend_from_slice(screen.pixels());
+    state::crc32(&bytes)
+}
+
+/// Replays the keys of a movie, releasing everything once it is over.
+#[derive(Debug, Clone)]
+pub struct Player {
+    keys: Vec<Keypad>,
+    next: usize,
+}
+
+impl Input for Player {
+    fn poll(&mut self, keypad: &mut Keypad) {
+        *keypad = self.keys.get(self.next).copied().unwrap_or_default();
+        self.next += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::headless::Headless;
+    use crate::io::NullAudio;
+
+    /// Draws a random sprite at a random place while key 5 is down.
+    const ROM: &str = "
+    loop:
+        LD V0, 5
+        SKP V0
+        JP loop
+        RND V1, 0x3F
+        RND V2, 0x1F
+        RND V3, 0x0F
+        LD F, V3
+        DRW V1, V2, 5
+        JP loop
+    ";
+
+    fn run(chip: &mut Chip, frames: usize, mut each: impl FnMut(&Chip, usize)) {
+        for frame in 0..frames {
+            for _ in 0..chip.instructions_per_frame() {
+                chip.cycle().unwrap();
+            }
+            each(chip, frame);
+        }
+    }
+
+    #[test]
+    fn replays_keys_and_randomness() {
+        let rom = assemble(ROM).unwrap().rom;
+        let headless = Headless::new();
+        let mut chip = headless.chip();
+        chip.set_instructions_per_frame(10);
+        chip.load(&rom).unwrap();
+        chip.start();
+        let mut movie = Movie::new(&chip, &rom);
+        run(&mut chip, 30, |chip, frame| {
+            headless.set_key(5, frame % 3 == 0);
+            movie.record(chip);
+        });
+        let bytes = movie.to_bytes();
+        let movie = Movie::from_bytes(&bytes).unwrap();
+        assert_eq!(movie.len(), 30);
+        assert!(movie.is_for(&rom));
+
+        let headless = Headless::new();
+        let mut replay = Chip::new(Box::new(headless.clone()), Box::new(movie.player()), Box::new(NullAudio));
+        movie.apply(&mut replay);
+        replay.load(&rom).unwrap();
+        replay.start();
+        let mut diverged = None;
+        run(&mut replay, 30, |chip, frame| {
+            if diverged.is_none() && !movie.matches(frame, chip) {
+                diverged = Some(frame);
+            }
+        });
+        assert_eq!(diverged, None);
+        assert_eq!(replay.screen().pixels(), chip.screen().pixels());
+    }
+
+    #[test]
+    fn another_seed_diverges() {
+        let rom = assemble(ROM).unwrap().rom;
+        let headless = Headless::new();
+        let mut chip = headless.chip();
+        chip.load(&rom).unwrap();
+        chip.start();
+        chip.set_random_state(1);
+        headless.press(5);
+        let mut movie = Movie::new(&chip, &rom);
+        run(&mut chip, 5, |chip, _| movie.record(chip));
+
+        movie.seed = 2;
+        let mut replay = Chip::new(Box::new(Headless::new()), Box::new(movie.player()), Box::new(NullAudio));
+        movie.apply(&mut replay);
+        replay.load(&rom).unwrap();
+        replay.start();
+        let mut matched = Vec::new();
+        run(&mut replay, 5, |chip, frame| matched.push(movie.matches(frame, chip)));
+        assert!(matched.contains(&false));
+    }
+
+    #[test]
+    fn damaged_movies_are_rejected() {
+        let chip = Headless::new().chip();
+        let bytes = Movie::new(&chip, &[]).to_bytes();
+        assert_eq!(Movie::from_bytes(&chip.save_state()), Err(StateErr::NotAState));
+        assert_eq!(Movie::from_bytes(&bytes[..bytes.len() - 1]), Err(StateErr::Truncated));
+    }
+}
@@ -0,0 +1,175 @@
+use std::str::FromStr;
+
+/// Behaviours that differ between the platforms that ran CHIP-8 programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8xy6/8xyE shift Vy and store the result in Vx, instead of shifting Vx in place.
+    pub shift_uses_vy: bool,
+    /// Fx55/Fx65 leave I pointing right after the last register accessed.
+    pub load_store_increments_i: bool,
+    /// Bnnn jumps to nnn + Vx, x being the highest nibble of nnn, instead of nnn + V0.
+    pub jump_uses_vx: bool,
+    /// 8xy1/8xy2/8xy3 reset VF to 0.
+    pub logic_resets_vf: bool,
+    /// Sprites crossing the edge of the screen wrap around instead of being clipped.
+    pub wrap_sprites: bool,
+    /// Fx0A stores the key as soon as it is pressed, instead of waiting for its release.
+    pub key_wait_on_press: bool,
+    /// Dxy0 draws a 16 rows high sprite in low resolution, instead of nothing.
+    /// High resolution always draws 16x16 sprites.
+    pub lores_large_sprites: bool,
+    /// Those low resolution sprites are 8 pixels wide, as on SUPER-CHIP, instead of 16.
+    pub narrow_large_sprites: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::cosmac_vip()
+    }
+}
+
+impl Quirks {
+    /// The original interpreter of the COSMAC VIP.
+    pub fn cosmac_vip() -> Quirks {
+        Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            logic_resets_vf: true,
+            wrap_sprites: false,
+            key_wait_on_press: false,
+            lores_large_sprites: false,
+            narrow_large_sprites: false,
+        }
+    }
+
+    /// CHIP-48 on the HP 48 calculators.
+    pub fn chip48() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            logic_resets_vf: false,
+            wrap_sprites: false,
+            key_wait_on_press: false,
+            lores_large_sprites: false,
+            narrow_large_sprites: false,
+        }
+    }
+
+    /// SUPER-CHIP 1.1, which kept the CHIP-48 behaviours and drew 8x16 sprites
+    /// for Dxy0 in low resolution.
+    pub fn superchip() -> Quirks {
+        Quirks { lores_large_sprites: true, narrow_large_sprites: true, ..Quirks::chip48() }
+    }
+
+    /// What most programs written for recent interpreters expect.
+    pub fn modern() -> Quirks {
+        Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: false,
+            logic_resets_vf: false,
+            wrap_sprites: true,
+            key_wait_on_press: false,
+            lores_large_sprites: true,
+            narrow_large_sprites: false,
+        }
+    }
+
+    /// Turns a single quirk on or off by name, see `Quirks::NAMES`.
+    /// Returns `false` if there is no quirk with that name.
+    pub fn set(&mut self, name: &str, value: bool) -> bool {
+        let quirk = match name {
+            "shift" => &mut self.shift_uses_vy,
+            "load-store" => &mut self.load_store_increments_i,
+            "jump" => &mut self.jump_uses_vx,
+            "vf-reset" => &mut self.logic_resets_vf,
+            "wrap" => &mut self.wrap_sprites,
+            "key-press" => &mut self.key_wait_on_press,
+            "dxy0" => &mut self.lores_large_sprites,
+            "dxy0-narrow" => &mut self.narrow_large_sprites,
+            _ => return false,
+        };
+        *quirk = value;
+        true
+    }
+
+    /// One bit per quirk, in the order of `Quirks::NAMES`.
+    pub fn to_bits(&self) -> u8 {
+        [
+            self.shift_uses_vy,
+            self.load_store_increments_i,
+            self.jump_uses_vx,
+            self.logic_resets_vf,
+            self.wrap_sprites,
+            self.key_wait_on_press,
+            self.lores_large_sprites,
+            self.narrow_large_sprites,
+        ]
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, on)| bits | (*on as u8) << i)
+    }
+
+    pub fn from_bits(bits: u8) -> Quirks {
+        let on = |i: u8| bits >> i & 1 == 1;
+        Quirks {
+            shift_uses_vy: on(0),
+            load_store_increments_i: on(1),
+            jump_uses_vx: on(2),
+            logic_resets_vf: on(3),
+            wrap_sprites: on(4),
+            key_wait_on_press: on(5),
+            lores_large_sprites: on(6),
+            narrow_large_sprites: on(7),
+        }
+    }
+
+    pub const NAMES: [&'static str; 8] = ["shift", "load-store", "jump", "vf-reset", "wrap", "key-press", "dxy0", "dxy0-narrow"];
+}
+
+/// The platforms `Quirks` has a preset for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    CosmacVip,
+    Chip48,
+    SuperChip,
+    Modern,
+}
+
+impl From<Preset> for Quirks {
+    fn from(preset: Preset) -> Self {
+        match preset {
+            Preset::CosmacVip => Quirks::cosmac_vip(),
+            Preset::Chip48 => Quirks::chip48(),
+            Preset::SuperChip => Quirks::superchip(),
+            Preset::Modern => Quirks::modern(),
+        }
+    }
+}
+
+impl Preset {
+    /// A speed the games of the platform expect, in instructions per 60 Hz frame.
+    pub fn instructions_per_frame(&self) -> u32 {
+        match self {
+            Preset::CosmacVip => 15,
+            Preset::Chip48 | Preset::SuperChip => 30,
+            Preset::Modern => 1000,
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "vip" | "cosmac" | "chip8" => Ok(Preset::CosmacVip),
+            "chip48" => Ok(Preset::Chip48),
+            "schip" | "superchip" => Ok(Preset::SuperChip),
+            "modern" => Ok(Preset::Modern),
+            _ => Err(()),
+        }
+    }
+}
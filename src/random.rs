@@ -0,0 +1,205 @@
+use crate::types::Data;
+
+/// Where Cxkk gets its random bytes from.
+///
+/// The whole state of a source is one number, so that save states and movies
+/// can restore it and replay the same bytes.
+pub trait RandomSource {
+    /// The next byte, before Cxkk masks it.
+    fn next_byte(&mut self) -> Data;
+
+    fn state(&self) -> u64;
+    fn set_state(&mut self, state: u64);
+
+    /// Called once per 60 Hz frame of emulated time.
+    fn frame(&mut self) {}
+}
+
+/// A small seeded generator (SplitMix64), the default source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prng {
+    state: u64,
+}
+
+impl Default for Prng {
+    /// Seeded from the operating system.
+    fn default() -> Self {
+        Prng::new(rand::random())
+    }
+}
+
+impl Prng {
+    pub fn new(seed: u64) -> Prng {
+        Prng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE5_E4B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl RandomSource for Prng {
+    fn next_byte(&mut self) -> Data {
+        (self.next_u64() >> 56) as Data
+    }
+
+    /// Equal to the seed until the first byte is drawn.
+    fn state(&self) -> u64 {
+        self.state
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.state = state;
+    }
+}
+
+/// Returns the given bytes in order, starting over once they have all been used.
+/// Meant for tests; the state is the position of the next byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scripted {
+    bytes: Vec<Data>,
+    next: usize,
+}
+
+impl Scripted {
+    /// Without any byte, always returns 0.
+    pub fn new(bytes: &[Data]) -> Scripted {
+        Scripted { bytes: bytes.to_vec(), next: 0 }
+    }
+}
+
+impl RandomSource for Scripted {
+    fn next_byte(&mut self) -> Data {
+        if self.bytes.is_empty() {
+            return 0;
+        }
+        let byte = self.bytes[self.next % self.bytes.len()];
+        self.next = (self.next + 1) % self.bytes.len();
+        byte
+    }
+
+    fn state(&self) -> u64 {
+        self.next as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.next = if self.bytes.is_empty() { 0 } else { (state % self.bytes.len() as u64) as usize };
+    }
+}
+
+/// The generator of the original COSMAC VIP interpreter.
+///
+/// Its 16-bit seed is incremented by each Cxkk and by the 60 Hz interrupt. The
+/// low byte points into the second page of the interpreter, whose byte is added
+/// to the high byte; that sum, rotated right through the carry and added to
+/// itself again, is the result and the new high byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Vip {
+    seed: u16,
+}
+
+/// Addresses 0x100 to 0x1FF of the VIP interpreter, which the generator reads
+/// as its table. The last 14 bytes are past the end of its code.
+const VIP_PAGE: [Data; 256] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x45, 0xA3, 0x98, 0x56, 0xD4, 0xF8, 0x81, 0xBC, 0xF8, 0x95, 0xAC,
+    0x22, 0xDC, 0x12, 0x56, 0xD4, 0x06, 0xB8, 0xD4, 0x06, 0xA8, 0xD4, 0x64, 0x0A, 0x01, 0xE6, 0x8A,
+    0xF4, 0xAA, 0x3B, 0x28, 0x9A, 0xFC, 0x01, 0xBA, 0xD4, 0xF8, 0x81, 0xBA, 0x06, 0xFA, 0x0F, 0xAA,
+    0x0A, 0xAA, 0xD4, 0xE6, 0x06, 0xBF, 0x93, 0xBE, 0xF8, 0x1B, 0xAE, 0x2A, 0x1A, 0xF8, 0x00, 0x5A,
+    0x0E, 0xF5, 0x3B, 0x4B, 0x56, 0x0A, 0xFC, 0x01, 0x5A, 0x30, 0x40, 0x4E, 0xF6, 0x3B, 0x3C, 0x9F,
+    0x56, 0x2A, 0x2A, 0xD4, 0x00, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x07, 0x5A, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x5B, 0x12, 0xD4, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x0A, 0x57, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x6B, 0x12, 0xD4, 0x15, 0x85, 0x22, 0x73, 0x95, 0x52, 0x25, 0x45, 0xA5, 0x86, 0xFA,
+    0x0F, 0xB5, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x82, 0x15, 0x15, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x88,
+    0xD4, 0x45, 0x07, 0x30, 0x8C, 0x45, 0x07, 0x30, 0x84, 0xE6, 0x62, 0x26, 0x45, 0xA3, 0x36, 0x88,
+    0xD4, 0x3E, 0x88, 0xD4, 0xF8, 0xF0, 0xA7, 0xE7, 0x45, 0xF4, 0xA5, 0x86, 0xFA, 0x0F, 0x3B, 0xB2,
+    0xFC, 0x01, 0xB5, 0xD4, 0x45, 0x56, 0xD4, 0x45, 0xE6, 0xF4, 0x56, 0xD4, 0x45, 0xFA, 0x0F, 0x3A,
+    0xC4, 0x07, 0x56, 0xD4, 0xAF, 0x22, 0xF8, 0xD3, 0x73, 0x8F, 0xF9, 0xF0, 0x52, 0xE6, 0x07, 0xD2,
+    0x56, 0xF8, 0xFF, 0xA6, 0xF8, 0x00, 0x7E, 0x56, 0xD4, 0x19, 0x89, 0xAE, 0x93, 0xBE, 0x99, 0xEE,
+    0xF4, 0x56, 0x76, 0xE6, 0xF4, 0xB9, 0x56, 0x45, 0xF2, 0x56, 0xD4, 0x45, 0xAA, 0x86, 0xFA, 0x0F,
+    0xBA, 0xD4, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xE0, 0x00, 0x4B,
+];
+
+impl Vip {
+    pub fn new(seed: u16) -> Vip {
+        Vip { seed }
+    }
+}
+
+impl RandomSource for Vip {
+    fn next_byte(&mut self) -> Data {
+        self.seed = self.seed.wrapping_add(1);
+        let [high, low] = self.seed.to_be_bytes();
+        let (sum, carry) = high.overflowing_add(VIP_PAGE[low as usize]);
+        let byte = ((sum >> 1) | ((carry as Data) << 7)).wrapping_add(sum);
+        self.seed = u16::from_be_bytes([byte, low]);
+        byte
+    }
+
+    fn state(&self) -> u64 {
+        self.seed as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.seed = state as u16;
+    }
+
+    fn frame(&mut self) {
+        self.seed = self.seed.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(source: &mut dyn RandomSource, count: usize) -> Vec<Data> {
+        (0..count).map(|_| source.next_byte()).collect()
+    }
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let first = draw(&mut Prng::new(42), 32);
+        assert_eq!(first, draw(&mut Prng::new(42), 32));
+        assert_ne!(first, draw(&mut Prng::new(43), 32));
+    }
+
+    #[test]
+    fn restoring_the_state_repeats_the_numbers() {
+        let sources: [Box<dyn RandomSource>; 3] = [Box::new(Prng::new(7)), Box::new(Scripted::new(&[1, 2, 3])), Box::new(Vip::new(0x1234))];
+        for mut source in sources {
+            draw(source.as_mut(), 5);
+            let state = source.state();
+            let next = draw(source.as_mut(), 8);
+            source.set_state(state);
+            assert_eq!(draw(source.as_mut(), 8), next);
+        }
+    }
+
+    #[test]
+    fn scripted_bytes_repeat() {
+        let mut scripted = Scripted::new(&[0xAA, 0x55]);
+        assert_eq!(draw(&mut scripted, 5), [0xAA, 0x55, 0xAA, 0x55, 0xAA]);
+        assert_eq!(draw(&mut Scripted::new(&[]), 2), [0, 0]);
+    }
+
+    #[test]
+    fn vip_follows_the_interpreter_routine() {
+        let mut vip = Vip::new(0);
+        assert_eq!(draw(&mut vip, 16), [0x00, 0x00, 0x00, 0x00, 0x67, 0x8F, 0xBA, 0x98, 0x22, 0xA7, 0xBC, 0x34, 0xC2, 0x02, 0x05, 0x3A]);
+        assert_eq!(vip.state(), 0x3A10);
+    }
+
+    #[test]
+    fn vip_counts_frames_in_the_seed() {
+        // 0x30 + VIP_PAGE[0x35] (0xBF) is 0xEF, rotated to 0x77 and added back.
+        let mut vip = Vip::new(0x3034);
+        assert_eq!(vip.next_byte(), 0x66);
+        assert_eq!(vip.state(), 0x6635);
+        vip.frame();
+        assert_eq!(vip.state(), 0x6636);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+use crate::c8::Chip;
+
+/// The last frames of a run, to step the emulation backwards.
+///
+/// Only the latest save state is kept whole. Each older frame is stored as its
+/// difference with the next one: the two states XORed together, then the runs of
+/// zeros, which is most of it, run-length encoded.
+#[derive(Debug, Clone)]
+pub struct Rewind {
+    latest: Option<Vec<u8>>,
+    /// Oldest first.
+    deltas: VecDeque<Vec<u8>>,
+    depth: usize,
+    budget: usize,
+    used: usize,
+}
+
+impl Rewind {
+    /// Keeps at most `depth` frames and `budget` bytes of history.
+    pub fn new(depth: usize, budget: usize) -> Rewind {
+        Rewind { latest: None, deltas: VecDeque::new(), depth, budget, used: 0 }
+    }
+
+    /// How many frames the machine can go back.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Bytes taken by the history, the latest state included.
+    pub fn memory_used(&self) -> usize {
+        self.used + self.latest.as_ref().map_or(0, Vec::len)
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.used = 0;
+    }
+
+    /// Records the machine, once per frame.
+    pub fn capture(&mut self, chip: &Chip) {
+        if self.depth == 0 {
+            return;
+        }
+        let state = chip.save_state();
+        if let Some(latest) = self.latest.replace(state) {
+            let delta = diff(self.latest.as_ref().unwrap(), &latest);
+            self.used += delta.len();
+            self.deltas.push_back(delta);
+        }
+        while self.deltas.len() > self.depth || self.memory_used() > self.budget {
+            match self.deltas.pop_front() {
+                Some(delta) => self.used -= delta.len(),
+                None => {
+                    // Not even one frame fits.
+                    self.latest = None;
+                    break;
+                },
+            }
+        }
+    }
+
+    /// Puts the machine back to the previous captured frame. Returns `false`
+    /// when there is no history left.
+    pub fn step_back(&mut self, chip: &mut Chip) -> bool {
+        let (Some(latest), Some(delta)) = (self.latest.as_ref(), self.deltas.pop_back()) else { return false };
+        self.used -= delta.len();
+        let older = patch(latest, &delta);
+        if chip.load_state(&older).is_err() {
+            self.clear();
+            return false;
+        }
+        self.latest = Some(older);
+        true
+    }
+}
+
+/// Encodes `older` against `newer`: its length, then `older ^ newer` with every
+/// run of zeros written as a zero followed by the run length on two bytes.
+fn diff(newer: &[u8], older: &[u8]) -> Vec<u8> {
+    let mut delta = (older.len() as u32).to_be_bytes().to_vec();
+    let mut zeros: u16 = 0;
+    for (i, byte) in older.iter().enumerate() {
+        let x = byte ^ newer.get(i).copied().unwrap_or(0);
+        if x == 0 && zeros < u16::MAX {
+            zeros += 1;
+            continue;
+        }
+        if zeros > 0 {
+            delta.push(0);
+            delta.extend(zeros.to_be_bytes());
+            zeros = 0;
+        }
+        if x == 0 {
+            zeros = 1;
+        } else {
+            delta.push(x);
+        }
+    }
+    if zeros > 0 {
+        delta.push(0);
+        delta.extend(zeros.to_be_bytes());
+    }
+    delta
+}
+
+/// The inverse of `diff`.
+fn patch(newer: &[u8], delta: &[u8]) -> Vec<u8> {
+    let len = u32::from_be_bytes(delta[0..4].try_into().unwrap()) as usize;
+    let mut older = Vec::with_capacity(len);
+    let mut i = 4;
+    while i < delta.len() {
+        if delta[i] == 0 {
+            let run = u16::from_be_bytes([delta[i + 1], delta[i + 2]]) as usize;
+            for _ in 0..run {
+                older.push(newer.get(older.len()).copied().unwrap_or(0));
+            }
+            i += 3;
+        } else {
+            older.push(delta[i] ^ newer.get(older.len()).copied().unwrap_or(0));
+            i += 1;
+        }
+    }
+    older
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::Headless;
+
+    #[test]
+    fn patch_undoes_diff() {
+        let newer = vec![0u8; 70000];
+        let mut older = newer.clone();
+        older[3] = 7;
+        older[69999] = 1;
+        older.push(5);
+        let delta = diff(&newer, &older);
+        assert!(delta.len() < 20);
+        assert_eq!(patch(&newer, &delta), older);
+        assert_eq!(patch(&older, &diff(&older, &newer)), newer);
+    }
+
+    #[test]
+    fn stepping_back_restores_the_previous_frames() {
+        let mut chip = Headless::new().chip();
+        chip.set_instructions_per_frame(1);
+        // ADD V0, 1; JP 0x200
+        chip.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        let mut rewind = Rewind::new(100, usize::MAX);
+        for _ in 0..10 {
+            chip.cycle().unwrap();
+            chip.cycle().unwrap();
+            rewind.capture(&chip);
+        }
+        assert_eq!((chip.registers()[0], rewind.len()), (10, 9));
+        assert!(rewind.step_back(&mut chip));
+        assert!(rewind.step_back(&mut chip));
+        assert_eq!(chip.registers()[0], 8);
+
+        chip.cycle().unwrap();
+        chip.cycle().unwrap();
+        rewind.capture(&chip);
+        assert!(rewind.step_back(&mut chip));
+        assert_eq!(chip.registers()[0], 8);
+    }
+
+    #[test]
+    fn history_stays_within_depth_and_budget() {
+        let mut chip = Headless::new().chip();
+        chip.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        let mut rewind = Rewind::new(5, usize::MAX);
+        for _ in 0..20 {
+            chip.cycle().unwrap();
+            rewind.capture(&chip);
+        }
+        assert_eq!(rewind.len(), 5);
+
+        let mut small = Rewind::new(100, chip.save_state().len() + 50);
+        for _ in 0..20 {
+            chip.cycle().unwrap();
+            small.capture(&chip);
+        }
+        assert!(small.memory_used() <= chip.save_state().len() + 50);
+        assert!(!small.is_empty() && small.len() < 20);
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+/// The length of a frame, when the timers tick and the screen is presented.
+pub const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// How far behind the wall clock the emulation may fall before the missed
+/// frames are dropped, e.g. after the window was dragged or the host slept.
+pub const MAX_LAG: u32 = 6;
+
+/// Paces the emulation at 60 frames per wall-clock second.
+///
+/// Deadlines are absolute, so time spent emulating and presenting does not add
+/// up into drift the way sleeping a fixed period after each step would.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    next: Instant,
+    fast_forward: u32,
+    fast: bool,
+    paused: bool,
+}
+
+impl Scheduler {
+    /// The first frame is due at `now`. While fast-forwarding, `fast_forward`
+    /// frames are emulated per frame of wall-clock time.
+    pub fn new(now: Instant, fast_forward: u32) -> Scheduler {
+        Scheduler { next: now, fast_forward: fast_forward.max(1), fast: false, paused: false }
+    }
+
+    /// When the next frame is due.
+    pub fn deadline(&self) -> Instant {
+        self.next
+    }
+
+    /// Sleeps until the next frame is due.
+    pub fn wait(&self) {
+        std::thread::sleep(self.next.saturating_duration_since(Instant::now()));
+    }
+
+    /// How many frames of wall-clock time have passed since the last call, at
+    /// most `MAX_LAG`. The caller presents the screen once when it is not 0.
+    pub fn tick(&mut self, now: Instant) -> u32 {
+        if now < self.next {
+            return 0;
+        }
+        let behind = 1 + (now - self.next).as_nanos() / FRAME.as_nanos();
+        if behind > MAX_LAG as u128 {
+            self.next = now + FRAME;
+            return MAX_LAG;
+        }
+        self.next += FRAME * behind as u32;
+        behind as u32
+    }
+
+    /// How many frames to emulate for `frames` of wall-clock time.
+    pub fn frames_to_run(&self, frames: u32) -> u32 {
+        match (self.paused, self.fast) {
+            (true, _) => 0,
+            (false, true) => frames * self.fast_forward,
+            (false, false) => frames,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn set_fast(&mut self, fast: bool) {
+        self.fast = fast;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_follow_the_wall_clock() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(start, 4);
+        assert_eq!(scheduler.tick(start), 1);
+        assert_eq!(scheduler.tick(start + FRAME / 2), 0);
+        // A late wake-up runs the missed frame without shifting the later deadlines.
+        assert_eq!(scheduler.tick(start + FRAME * 2 + FRAME / 2), 2);
+        assert_eq!(scheduler.deadline(), start + FRAME * 3);
+        assert_eq!(scheduler.tick(start + FRAME * 3), 1);
+    }
+
+    #[test]
+    fn long_stalls_are_dropped() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(start, 4);
+        let later = start + Duration::from_secs(5);
+        assert_eq!(scheduler.tick(later), MAX_LAG);
+        assert_eq!(scheduler.deadline(), later + FRAME);
+    }
+
+    #[test]
+    fn pause_and_fast_forward() {
+        let mut scheduler = Scheduler::new(Instant::now(), 4);
+        assert_eq!(scheduler.frames_to_run(2), 2);
+        scheduler.set_fast(true);
+        assert_eq!(scheduler.frames_to_run(2), 8);
+        scheduler.set_paused(true);
+        assert_eq!(scheduler.frames_to_run(2), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::*;
+use crate::{err::C8Err, types::*};
 
 const MAX : usize = 16;
 
@@ -7,19 +7,33 @@ pub struct Stack {
     vector : Vec<AddressLong>
 }
 
+impl Default for Stack {
+    fn default() -> Self {
+        Stack::new()
+    }
+}
+
 impl Stack {
     pub fn new () -> Stack {
         Stack { vector: Vec::new() }
     }
 
-    pub fn pop (&mut self) -> Option<AddressLong> {
-        self.vector.pop()
+    pub fn pop (&mut self) -> Result<AddressLong, C8Err> {
+        self.vector.pop().ok_or(C8Err::StackUnderflow)
     }
 
-    pub fn push (&mut self, data : AddressLong) {
-        match self.vector.len()+1 {
-            MAX..   => panic!("Stack Overflow"),
-            _       => self.vector.push(data)
+    pub fn push (&mut self, data : AddressLong) -> Result<(), C8Err> {
+        match self.vector.len() {
+            MAX..   => Err(C8Err::StackOverflow),
+            _       => {
+                self.vector.push(data);
+                Ok(())
+            }
         }
     }
-}
\ No newline at end of file
+
+    /// The return addresses, oldest first.
+    pub fn entries (&self) -> &[AddressLong] {
+        &self.vector
+    }
+}
@@ -0,0 +1,227 @@
+use std::{error, fmt};
+
+/// Every save state starts with these bytes.
+pub const MAGIC: [u8; 4] = *b"C8ST";
+
+/// Bumped whenever the layout of the machine changes.
+pub const VERSION: u16 = 3;
+
+/// Magic, version, payload length and payload checksum. Movies use the same header.
+const HEADER_SIZE: usize = 14;
+
+/// Why a save state, or a movie, could not be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateErr {
+    NotAState,
+    UnsupportedVersion { version: u16, expected: u16 },
+    Truncated,
+    ChecksumMismatch,
+    /// The checksum matches but a value makes no sense, e.g. a stack deeper than 16.
+    Invalid { what: &'static str },
+}
+
+impl fmt::Display for StateErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateErr::NotAState => write!(f, "not a save state or movie of this emulator"),
+            StateErr::UnsupportedVersion { version, expected } => write!(f, "version {version} is not supported, expected {expected}"),
+            StateErr::Truncated => write!(f, "the file is truncated"),
+            StateErr::ChecksumMismatch => write!(f, "the file is corrupted"),
+            StateErr::Invalid { what } => write!(f, "invalid content: {what}"),
+        }
+    }
+}
+
+impl error::Error for StateErr {}
+
+/// Builds a save state, big-endian, adding the header once finished.
+#[derive(Debug, Default)]
+pub(crate) struct Writer {
+    payload: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Writer {
+        Writer::default()
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.payload.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.payload.extend(value.to_be_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.payload.extend(value.to_be_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.payload.extend(value.to_be_bytes());
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.payload.extend_from_slice(bytes);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.finish_as(MAGIC, VERSION)
+    }
+
+    pub fn finish_as(self, magic: [u8; 4], version: u16) -> Vec<u8> {
+        let mut state = Vec::with_capacity(HEADER_SIZE + self.payload.len());
+        state.extend(magic);
+        state.extend(version.to_be_bytes());
+        state.extend((self.payload.len() as u32).to_be_bytes());
+        state.extend(crc32(&self.payload).to_be_bytes());
+        state.extend(self.payload);
+        state
+    }
+}
+
+/// Reads the payload of a save state whose header has been checked.
+#[derive(Debug)]
+pub(crate) struct Reader<'a> {
+    payload: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn open(state: &'a [u8]) -> Result<Reader<'a>, StateErr> {
+        Reader::open_as(state, MAGIC, VERSION)
+    }
+
+    pub fn open_as(state: &'a [u8], magic: [u8; 4], expected: u16) -> Result<Reader<'a>, StateErr> {
+        if state.len() < HEADER_SIZE || state[0..4] != magic {
+            return Err(StateErr::NotAState);
+        }
+        let version = u16::from_be_bytes([state[4], state[5]]);
+        if version != expected {
+            return Err(StateErr::UnsupportedVersion { version, expected });
+        }
+        let len = u32::from_be_bytes(state[6..10].try_into().unwrap()) as usize;
+        let checksum = u32::from_be_bytes(state[10..14].try_into().unwrap());
+        let payload = &state[HEADER_SIZE..];
+        if payload.len() < len {
+            return Err(StateErr::Truncated);
+        }
+        if payload.len() > len || crc32(payload) != checksum {
+            return Err(StateErr::ChecksumMismatch);
+        }
+        Ok(Reader { payload })
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateErr> {
+        if self.payload.len() < len {
+            return Err(StateErr::Truncated);
+        }
+        let (bytes, rest) = self.payload.split_at(len);
+        self.payload = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, StateErr> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, StateErr> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateErr::Invalid { what: "boolean out of range" }),
+        }
+    }
+
+    pub fn u16(&mut self) -> Result<u16, StateErr> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, StateErr> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, StateErr> {
+        Ok(u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N], StateErr> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    /// Checks that the whole payload has been read.
+    pub fn finish(self) -> Result<(), StateErr> {
+        if self.payload.is_empty() {
+            Ok(())
+        } else {
+            Err(StateErr::Invalid { what: "unexpected data at the end" })
+        }
+    }
+}
+
+/// CRC-32 as used by zip and PNG.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { crc >> 1 ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::Headless;
+
+    #[test]
+    fn crc32_matches_the_reference() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn loading_a_state_restores_the_machine() {
+        let headless = Headless::new();
+        let mut chip = headless.chip();
+        chip.start();
+        // LD V0, 5; CALL 0x208; JP 0x204; (0x208) ADD V0, 1; DRW V0, V0, 5; RET
+        chip.load(&[0x60, 0x05, 0x22, 0x08, 0x12, 0x04, 0x00, 0x00, 0x70, 0x01, 0xD0, 0x05, 0x00, 0xEE]).unwrap();
+        for _ in 0..3 {
+            chip.cycle().unwrap();
+        }
+        headless.press(0xB);
+        let state = chip.save_state();
+        let screen = chip.screen().clone();
+
+        for _ in 0..20 {
+            chip.cycle().unwrap();
+        }
+        chip.set_register(0, 0xFF);
+        chip.load_state(&state).unwrap();
+
+        assert_eq!((chip.pc(), chip.registers()[0], chip.stack()), (0x20A, 6, &[0x204][..]));
+        assert_eq!(chip.screen().pixels(), screen.pixels());
+        assert_eq!(chip.save_state(), state);
+    }
+
+    #[test]
+    fn damaged_states_are_rejected() {
+        let mut chip = Headless::new().chip();
+        let state = chip.save_state();
+
+        let mut corrupted = state.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(chip.load_state(&corrupted), Err(StateErr::ChecksumMismatch));
+        assert_eq!(chip.load_state(&state[..state.len() - 1]), Err(StateErr::Truncated));
+        assert_eq!(chip.load_state(b"CHIP-8"), Err(StateErr::NotAState));
+
+        let mut newer = state.clone();
+        newer[5] += 1;
+        assert_eq!(chip.load_state(&newer), Err(StateErr::UnsupportedVersion { version: VERSION + 1, expected: VERSION }));
+    }
+}
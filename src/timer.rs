@@ -1,36 +1,42 @@
-use std::{thread, time::Duration, sync::{Arc, atomic::AtomicU8}};
-use std::sync::atomic::Ordering::Relaxed;
-
 use crate::types::Data;
 
-const PERIOD : u64 = 17;
-
+/// A delay or sound timer, decremented by the `Chip` at 60 Hz of emulated time.
+#[derive(Debug, Clone, Default)]
 pub struct Timer {
-    value   : Arc<AtomicU8>,
+    value   : Data,
 }
 
-impl<> Timer {
+impl Timer {
     pub fn new() -> Timer {
-        Timer { value: Arc::new(AtomicU8::new(0)) }
+        Timer { value: 0 }
     }
 
-    pub fn set(&self, value: Data) {
-        self.value.store(value, Relaxed);
+    pub fn set(&mut self, value: Data) {
+        self.value = value;
     }
 
     pub fn get(&self) -> u8 {
-        self.value.load(Relaxed)
+        self.value
     }
 
-    pub fn start(&self) {
-        let value = Arc::clone(&self.value);
-        thread::spawn(move || {
-            loop {
-                if value.load(Relaxed) > 0 {
-                    value.store(value.load(Relaxed) - 1, Relaxed);
-                } else {}
-                thread::sleep(Duration::from_millis(PERIOD));
-            }
-        });
+    /// Counts down by one, stopping at zero.
+    pub fn tick(&mut self) {
+        self.value = self.value.saturating_sub(1);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_counts_down_to_zero() {
+        let mut timer = Timer::new();
+        timer.set(2);
+        timer.tick();
+        assert_eq!(timer.get(), 1);
+        timer.tick();
+        timer.tick();
+        assert_eq!(timer.get(), 0);
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+
+use crate::disasm::{Decoded, Syntax};
+use crate::types::{AddressLong, Data};
+
+/// Writes one line per executed instruction, with the machine as it was just
+/// before executing it:
+///
+/// ```text
+/// 0208  D125       I=0050  V=05 10 0a 03 00 00 00 00 00 00 00 00 00 00 00 00  DRW V1, V2, 5
+/// ```
+///
+/// Every field has a fixed width, so that traces of two runs, or of another
+/// emulator printing the same columns, can be compared with `diff`.
+pub struct Tracer {
+    out: Box<dyn Write>,
+}
+
+impl Tracer {
+    pub fn new(out: Box<dyn Write>) -> Tracer {
+        Tracer { out }
+    }
+
+    /// Traces the instruction at `pc` in `memory`.
+    pub fn instruction(&mut self, memory: &[Data], pc: AddressLong, i: AddressLong, registers: &[Data; 16]) -> io::Result<()> {
+        let (raw, mnemonic) = match Decoded::at(memory, pc as usize) {
+            Some(decoded) => (decoded.raw(), decoded.mnemonic(Syntax::Cowgod)),
+            None => (String::new(), String::new()),
+        };
+        let registers: Vec<String> = registers.iter().map(|v| format!("{v:02x}")).collect();
+        writeln!(self.out, "{pc:04x}  {raw:<9}  I={i:04x}  V={}  {mnemonic}", registers.join(" "))
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// A `Write` whose content can still be read once boxed.
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn one_line_per_instruction() {
+        let out = Shared::default();
+        let mut chip = crate::headless::Headless::new().chip();
+        chip.set_tracer(Some(Tracer::new(Box::new(out.clone()))));
+        // LD V1, 0x10; LD I, 0x300; F000 0x1234
+        chip.load(&[0x61, 0x10, 0xA3, 0x00, 0xF0, 0x00, 0x12, 0x34]).unwrap();
+        for _ in 0..3 {
+            chip.cycle().unwrap();
+        }
+        let zeros = " 00".repeat(14);
+        let expected = format!(
+            "0200  6110       I=0000  V=00 00{zeros}  LD V1, 0x10\n\
+             0202  A300       I=0000  V=00 10{zeros}  LD I, 0x300\n\
+             0204  F000 1234  I=0300  V=00 10{zeros}  LD I, LONG 0x1234\n"
+        );
+        assert_eq!(String::from_utf8(out.0.borrow().clone()).unwrap(), expected);
+    }
+}
@@ -1,3 +1,2 @@
 pub type AddressLong = u16;
-pub type AddressShort = u8;
 pub type Data = u8;
\ No newline at end of file
@@ -0,0 +1,144 @@
+//! Runs test ROMs without a window and compares the final screen with a golden
+//! image of `tests/golden`.
+//!
+//! The reference tests run the binaries of Timendus' CHIP-8 test suite (MIT)
+//! from `tests/roms/timendus`, loaded byte for byte with no assembler in the
+//! way, against goldens taken from a reference emulator. They are ignored
+//! until both are checked in, see `tests/roms/timendus/README.md`; this
+//! emulator never writes those goldens.
+//!
+//! The other tests are extra: ROMs written for this repository in the spirit
+//! of the same suites and assembled by `c8::asm`. Their goldens are blessed by
+//! this emulator, so every check draws a tick or a cross and a cross in a
+//! golden is a bug, except in the quirk ROM where each mark tells which of two
+//! behaviours was seen. After an intended change of their output,
+//! `C8_BLESS=1 cargo test --test conformance` rewrites them.
+
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use c8::asm::assemble_file;
+use c8::Chip;
+use c8::quirks::{Preset, Quirks};
+
+fn path(dir: &str, file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join(dir).join(file)
+}
+
+/// The screen after `frames` frames of `bytes`, as `Screen::to_text`.
+/// `platform` is written at 0x1FF, where the Timendus ROMs look for a choice
+/// made ahead of their menu.
+fn run(rom: &str, bytes: &[u8], preset: Preset, platform: Option<u8>, frames: u32) -> String {
+    let mut chip = Chip::from_rom(bytes).unwrap();
+    if let Some(platform) = platform {
+        chip.memory_mut().write(platform, 0x1FF).unwrap();
+    }
+    chip.set_quirks(Quirks::from(preset));
+    chip.set_instructions_per_frame(preset.instructions_per_frame());
+    chip.set_random_state(0);
+    if let Err(e) = chip.run_frames(frames) {
+        panic!("{rom} crashed at {:#05x}: {e}", chip.pc());
+    }
+    chip.screen().to_text()
+}
+
+/// The expected and actual screens side by side, differing rows marked with `>`.
+fn diff(expected: &str, actual: &str) -> String {
+    let mut out = String::new();
+    let width = expected.lines().next().map_or(0, str::len);
+    out += &format!("  {:<width$}   actual\n", "expected");
+    let rows = expected.lines().count().max(actual.lines().count());
+    for row in 0..rows {
+        let e = expected.lines().nth(row).unwrap_or("");
+        let a = actual.lines().nth(row).unwrap_or("");
+        let marker = if e == a { ' ' } else { '>' };
+        out += &format!("{marker} {e:<width$}   {a}\n");
+    }
+    out
+}
+
+/// Checks one of the ROMs of this repository, blessing its golden with `C8_BLESS`.
+fn check(rom: &str, golden: &str, preset: Preset, frames: u32) {
+    let bytes = match assemble_file(path("roms", &format!("{rom}.asm"))) {
+        Ok(assembly) => assembly.rom,
+        Err(e) => panic!("{e}"),
+    };
+    let actual = run(rom, &bytes, preset, None, frames);
+    let golden = path("golden", &format!("{golden}.txt"));
+    if env::var_os("C8_BLESS").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        return;
+    }
+    compare(rom, &golden, &actual);
+}
+
+/// Checks a binary of the Timendus suite against a golden of a reference emulator.
+fn check_reference(rom: &str, golden: &str, preset: Preset, platform: Option<u8>, frames: u32) {
+    let file = path("roms/timendus", &format!("{rom}.ch8"));
+    let bytes = fs::read(&file).unwrap_or_else(|e| panic!("{}: {e}", file.display()));
+    let actual = run(rom, &bytes, preset, platform, frames);
+    compare(rom, &path("golden/timendus", &format!("{golden}.txt")), &actual);
+}
+
+fn compare(rom: &str, golden: &Path, actual: &str) {
+    let expected = fs::read_to_string(golden).unwrap_or_else(|e| panic!("{}: {e}", golden.display()));
+    if expected != actual {
+        panic!("the screen of {rom} differs from {}\n{}", golden.display(), diff(&expected, actual));
+    }
+}
+
+#[test]
+#[ignore = "needs the Timendus binaries and reference goldens, see tests/roms/timendus/README.md"]
+fn timendus_chip8_logo() {
+    check_reference("1-chip8-logo", "chip8-logo", Preset::CosmacVip, None, 60);
+}
+
+#[test]
+#[ignore = "needs the Timendus binaries and reference goldens, see tests/roms/timendus/README.md"]
+fn timendus_ibm_logo() {
+    check_reference("2-ibm-logo", "ibm-logo", Preset::CosmacVip, None, 60);
+}
+
+#[test]
+#[ignore = "needs the Timendus binaries and reference goldens, see tests/roms/timendus/README.md"]
+fn timendus_corax() {
+    check_reference("3-corax+", "corax+", Preset::CosmacVip, None, 60);
+}
+
+#[test]
+#[ignore = "needs the Timendus binaries and reference goldens, see tests/roms/timendus/README.md"]
+fn timendus_flags() {
+    check_reference("4-flags", "flags", Preset::CosmacVip, None, 120);
+}
+
+#[test]
+#[ignore = "needs the Timendus binaries and reference goldens, see tests/roms/timendus/README.md"]
+fn timendus_quirks_vip() {
+    check_reference("5-quirks", "quirks-vip", Preset::CosmacVip, Some(1), 600);
+}
+
+#[test]
+fn logo() {
+    check("logo", "logo", Preset::CosmacVip, 20);
+}
+
+#[test]
+fn opcodes() {
+    check("opcodes", "opcodes", Preset::CosmacVip, 60);
+    check("opcodes", "opcodes", Preset::SuperChip, 60);
+}
+
+#[test]
+fn flags() {
+    check("flags", "flags", Preset::CosmacVip, 20);
+}
+
+#[test]
+fn quirks_vip() {
+    check("quirks", "quirks-vip", Preset::CosmacVip, 20);
+}
+
+#[test]
+fn quirks_modern() {
+    check("quirks", "quirks-modern", Preset::Modern, 20);
+}
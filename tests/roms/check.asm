@@ -0,0 +1,22 @@
+; Included at the end of the test ROMs: `CALL ok` draws a tick and `CALL ko` a
+; cross, left to right and then on the next row, 11 marks per row.
+; Uses VA and VB for the position, I and VF.
+ok:     LD I, tick
+        JP mark
+ko:     LD I, cross
+mark:   DRW VA, VB, 4
+        ADD VA, 5
+        SE VA, 55
+        RET
+        LD VA, 0
+        ADD VB, 6
+        RET
+
+tick:   DB 0b00010000
+        DB 0b00100000
+        DB 0b10100000
+        DB 0b01000000
+cross:  DB 0b10010000
+        DB 0b01100000
+        DB 0b01100000
+        DB 0b10010000
@@ -0,0 +1,133 @@
+; One mark per check of VF, in the spirit of the flags test: the value of the
+; result, then the value of VF, for each arithmetic instruction. V3 keeps VF
+; since drawing the marks changes it.
+
+; 8xy4 without and with a carry
+        LD V0, 0x10
+        LD V1, 0x20
+        ADD V0, V1
+        LD V3, VF
+        SE V0, 0x30
+        CALL ko
+        SNE V0, 0x30
+        CALL ok
+        SE V3, 0
+        CALL ko
+        SNE V3, 0
+        CALL ok
+        LD V0, 0xF0
+        LD V1, 0x20
+        ADD V0, V1
+        LD V3, VF
+        SE V0, 0x10
+        CALL ko
+        SNE V0, 0x10
+        CALL ok
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+; 8xy5 without and with a borrow, equal values do not borrow
+        LD V0, 0x30
+        LD V1, 0x10
+        SUB V0, V1
+        LD V3, VF
+        SE V0, 0x20
+        CALL ko
+        SNE V0, 0x20
+        CALL ok
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+        LD V0, 0x10
+        LD V1, 0x30
+        SUB V0, V1
+        LD V3, VF
+        SE V0, 0xE0
+        CALL ko
+        SNE V0, 0xE0
+        CALL ok
+        SE V3, 0
+        CALL ko
+        SNE V3, 0
+        CALL ok
+        LD V0, 0x10
+        LD V1, 0x10
+        SUB V0, V1
+        LD V3, VF
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+; 8xy7 without and with a borrow
+        LD V0, 0x10
+        LD V1, 0x30
+        SUBN V0, V1
+        LD V3, VF
+        SE V0, 0x20
+        CALL ko
+        SNE V0, 0x20
+        CALL ok
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+        LD V0, 0x30
+        LD V1, 0x10
+        SUBN V0, V1
+        LD V3, VF
+        SE V0, 0xE0
+        CALL ko
+        SNE V0, 0xE0
+        CALL ok
+        SE V3, 0
+        CALL ko
+        SNE V3, 0
+        CALL ok
+; 8xy6, 8xyE: the bit shifted out
+        LD V0, 0x81
+        SHR V0, V0
+        LD V3, VF
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+        LD V0, 0x81
+        SHL V0, V0
+        LD V3, VF
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+; VF as the destination: the flag wins over the result
+        LD VF, 0xF0
+        LD V1, 0x20
+        ADD VF, V1
+        SE VF, 1
+        CALL ko
+        SNE VF, 1
+        CALL ok
+        LD VF, 0x10
+        LD V1, 0x30
+        SUB VF, V1
+        SE VF, 0
+        CALL ko
+        SNE VF, 0
+        CALL ok
+; 7xkk wraps around and leaves VF alone
+        LD VF, 0x55
+        LD V0, 0xFF
+        ADD V0, 2
+        LD V3, VF
+        SE V0, 1
+        CALL ko
+        SNE V0, 1
+        CALL ok
+        SE V3, 0x55
+        CALL ko
+        SNE V3, 0x55
+        CALL ok
+end:    JP end
+
+INCLUDE "check.asm"
@@ -0,0 +1,34 @@
+; A banner in the spirit of the IBM logo ROM: only CLS, LD I, LD Vx, ADD Vx and
+; DRW, then the 16 hexadecimal digits of the font on two rows.
+        CLS
+        LD V0, 12
+        LD V1, 4
+        LD I, c
+        DRW V0, V1, 15
+        ADD V0, 9
+        LD I, eight
+        DRW V0, V1, 15
+        ADD V0, 12
+        LD I, bar
+        DRW V0, V1, 15
+        ADD V0, 8
+        DRW V0, V1, 15
+
+        LD V0, 0
+        LD V1, 0
+        LD V2, 21
+digits: LD F, V0
+        DRW V1, V2, 5
+        ADD V0, 1
+        ADD V1, 8
+        SE V1, 64
+        JP digits
+        LD V1, 0
+        ADD V2, 6
+        SE V0, 16
+        JP digits
+end:    JP end
+
+c:      DB 0x7E, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC0, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x7E
+eight:  DB 0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0x7E
+bar:    DB 0xF0, 0xF0, 0x00, 0xF0, 0xF0, 0x00, 0xF0, 0xF0, 0x00, 0xF0, 0xF0, 0x00, 0xF0, 0xF0, 0x00
@@ -0,0 +1,217 @@
+; One mark per check, in the spirit of the corax+ opcode test. Only
+; behaviours that do not depend on a quirk are checked: the shifts shift Vx
+; in place, and VF is kept in V3 right after the instruction since drawing
+; the marks changes it.
+
+        JP main
+
+; Kept first so that Bxnn of the SUPER-CHIP, which adds V2 for 0x2nn, finds
+; the offset in V2.
+table:  JP table
+        LD V0, 0x77
+        JP back
+
+set:    LD V0, 0x99
+        RET
+
+; 3xkk, 4xkk
+main:   LD V0, 0x2A
+        SE V0, 0x2A
+        CALL ko
+        SNE V0, 0x2A
+        CALL ok
+        SNE V0, 0x2B
+        CALL ko
+        SE V0, 0x2B
+        CALL ok
+; 5xy0, 9xy0
+        LD V1, 0x2A
+        SE V0, V1
+        CALL ko
+        SNE V0, V1
+        CALL ok
+        LD V1, 0x2B
+        SNE V0, V1
+        CALL ko
+        SE V0, V1
+        CALL ok
+; 7xkk without overflow
+        LD V0, 0x10
+        ADD V0, 0x22
+        SE V0, 0x32
+        CALL ko
+        SNE V0, 0x32
+        CALL ok
+; 8xy0, 8xy1, 8xy2, 8xy3
+        LD V1, 0x0F
+        LD V0, V1
+        SE V0, 0x0F
+        CALL ko
+        SNE V0, 0x0F
+        CALL ok
+        LD V1, 0xF0
+        OR V0, V1
+        SE V0, 0xFF
+        CALL ko
+        SNE V0, 0xFF
+        CALL ok
+        LD V1, 0x3C
+        AND V0, V1
+        SE V0, 0x3C
+        CALL ko
+        SNE V0, 0x3C
+        CALL ok
+        LD V1, 0xFF
+        XOR V0, V1
+        SE V0, 0xC3
+        CALL ko
+        SNE V0, 0xC3
+        CALL ok
+; 2nnn, 00EE
+        LD V0, 0
+        CALL set
+        SE V0, 0x99
+        CALL ko
+        SNE V0, 0x99
+        CALL ok
+; Bnnn, with the same offset in V0 and V2 as either may be added
+        LD V0, 2
+        LD V2, 2
+        JP V0, table
+back:   SE V0, 0x77
+        CALL ko
+        SNE V0, 0x77
+        CALL ok
+; Annn, Fx1E, Fx33
+        LD I, scratch
+        LD V0, 1
+        ADD I, V0
+        LD V0, 234
+        LD B, V0
+        LD V2, [I]
+        SE V1, 3
+        CALL ko
+        SNE V1, 3
+        CALL ok
+; Fx55, Fx65
+        LD I, scratch
+        LD V0, 0x11
+        LD V1, 0x22
+        LD V2, 0x33
+        LD [I], V2
+        LD I, scratch
+        LD V0, 0
+        LD V2, 0
+        LD V2, [I]
+        SE V2, 0x33
+        CALL ko
+        SNE V2, 0x33
+        CALL ok
+; Fx15, Fx07: the delay timer counts down
+        LD V0, 30
+        LD DT, V0
+        LD V0, DT
+        SNE V0, 0
+        CALL ko
+        SE V0, 0
+        CALL ok
+; 7xkk wraps around and leaves VF alone
+        LD VF, 5
+        LD V0, 0xF0
+        ADD V0, 0x20
+        LD V3, VF
+        SE V0, 0x10
+        CALL ko
+        SNE V0, 0x10
+        CALL ok
+        SE V3, 5
+        CALL ko
+        SNE V3, 5
+        CALL ok
+; 8xy4 with a carry
+        LD V0, 0xF0
+        LD V1, 0x20
+        ADD V0, V1
+        LD V3, VF
+        SE V0, 0x10
+        CALL ko
+        SNE V0, 0x10
+        CALL ok
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+; 8xy5 with a borrow
+        LD V0, 0x10
+        LD V1, 0x20
+        SUB V0, V1
+        LD V3, VF
+        SE V0, 0xF0
+        CALL ko
+        SNE V0, 0xF0
+        CALL ok
+        SE V3, 0
+        CALL ko
+        SNE V3, 0
+        CALL ok
+; 8xy7 without a borrow
+        LD V0, 0x10
+        LD V1, 0x20
+        SUBN V0, V1
+        LD V3, VF
+        SE V0, 0x10
+        CALL ko
+        SNE V0, 0x10
+        CALL ok
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+; 8xy6
+        LD V0, 0x81
+        SHR V0, V0
+        LD V3, VF
+        SE V0, 0x40
+        CALL ko
+        SNE V0, 0x40
+        CALL ok
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+; 8xyE
+        LD V0, 0x41
+        SHL V0, V0
+        LD V3, VF
+        SE V0, 0x82
+        CALL ko
+        SNE V0, 0x82
+        CALL ok
+        SE V3, 0
+        CALL ko
+        SNE V3, 0
+        CALL ok
+; Dxyn sets VF when it erases a pixel, away from the marks
+        LD I, dot
+        LD V0, 60
+        LD V1, 30
+        DRW V0, V1, 1
+        LD V3, VF
+        SE V3, 0
+        CALL ko
+        SNE V3, 0
+        CALL ok
+        LD I, dot
+        DRW V0, V1, 1
+        LD V3, VF
+        SE V3, 1
+        CALL ko
+        SNE V3, 1
+        CALL ok
+end:    JP end
+
+scratch: DB 0, 0, 0, 0, 0
+
+dot:    DB 0b10000000
+
+INCLUDE "check.asm"
@@ -0,0 +1,49 @@
+; A tick when a quirk behaves as on the COSMAC VIP, a cross when it behaves
+; as on later interpreters, then a sprite drawn across the right edge.
+
+; shift: 8xy6 shifts Vy into Vx
+        LD V0, 0x01
+        LD V1, 0x08
+        SHR V0, V1
+        SE V0, 0x04
+        CALL ko
+        SNE V0, 0x04
+        CALL ok
+; load-store: Fx55 leaves I past the last register
+        LD V0, 0x42
+        LD I, scratch
+        LD [I], V1
+        LD V0, [I]
+        SE V0, 0
+        CALL ko
+        SNE V0, 0
+        CALL ok
+; jump: Bnnn adds V0, not Vx, x being 2 as the ROM is at 0x200
+        LD V0, 0
+        LD V2, 2
+        JP V0, jumps
+jumps:  JP vip
+        JP later
+vip:    CALL ok
+        JP vfreset
+later:  CALL ko
+; vf-reset: 8xy1 clears VF
+vfreset:
+        LD VF, 1
+        OR V0, V0
+        LD V3, VF
+        SE V3, 0
+        CALL ko
+        SNE V3, 0
+        CALL ok
+; wrap: half of the sprite comes back on the left
+        LD V0, 60
+        LD V1, 20
+        LD I, block
+        DRW V0, V1, 4
+end:    JP end
+
+block:  DB 0xFF, 0xFF, 0xFF, 0xFF
+scratch: DB 0, 0, 0
+
+INCLUDE "check.asm"